@@ -4,7 +4,7 @@ fn main()
 	let url = std::env::args().nth(1)
 		.expect("url expected as parameter");
 	let mut e = idcurl::get(&url)
+		.and_then(|r| r.error_for_status())
 		.expect("error making request");
-	assert!(e.status().is_success());
 	e.copy_to(&mut std::io::stdout()).unwrap();
 }
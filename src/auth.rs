@@ -0,0 +1,357 @@
+use libc::c_ulong;
+
+use crate::*;
+use crate::client::cr;
+use crate::header::*;
+use crate::request::CurlList;
+
+/// Credentials for HTTP authentication
+///
+/// The secrets are overwritten with zeros when this is dropped,
+/// and are never shown by `Debug`.
+///
+/// ```no_run
+/// let response = idcurl::Request::get("https://example.com/private".to_string())
+///     .auth(idcurl::Auth::Digest
+///     {
+///         username: "user".to_string(),
+///         password: "secret".to_string(),
+///     })
+///     .send()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum Auth
+{
+	/// Send the username and password with every request
+	///
+	/// They are only encoded, not encrypted, so this should
+	/// only be used over https.
+	Basic
+	{
+		username: String,
+		password: String,
+	},
+	/// Answer the server's challenge with a digest of the password
+	Digest
+	{
+		username: String,
+		password: String,
+	},
+	/// Send an OAuth 2.0 bearer token
+	Bearer(String),
+	/// Use Kerberos or SPNEGO with the credentials of the current user
+	///
+	/// This needs a curl that was built with GSS-API.
+	Negotiate,
+	/// Use the most secure scheme that the server offers
+	///
+	/// This costs an extra request to learn which schemes
+	/// the server accepts.
+	Any
+	{
+		username: String,
+		password: String,
+	},
+	/// Sign the request with AWS Signature Version 4
+	///
+	/// See [`Request::aws_sigv4`].
+	AwsSigV4
+	{
+		provider: String,
+		region: String,
+		service: String,
+		credentials: AwsCredentials,
+	},
+}
+
+/// Whether the username and password come from a `.netrc` file
+///
+/// The file is `~/.netrc` unless another is set with
+/// [`Request::netrc_file`]. It has the credentials for each host:
+///
+/// ```text
+/// machine api.example.com login user password secret
+/// ```
+///
+/// If the URL has a username, only that login is looked for.
+/// Credentials from an [`Auth`] take precedence over the file.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum Netrc
+{
+	/// Don't read the file
+	#[default]
+	Ignored,
+	/// Use the file when the URL doesn't have a password,
+	/// but carry on without it if it can't be read
+	Optional,
+	/// Use the file even if the URL has a password, which is
+	/// ignored, and fail if the file can't be read
+	Required,
+}
+
+impl Netrc
+{
+	pub(crate) fn curl(self) -> c_ulong
+	{
+		match self
+		{
+			Netrc::Ignored => sys::CURL_NETRC_IGNORED,
+			Netrc::Optional => sys::CURL_NETRC_OPTIONAL,
+			Netrc::Required => sys::CURL_NETRC_REQUIRED,
+		}
+	}
+}
+
+/// The keys that sign a request for AWS or a compatible service
+///
+/// The secrets are overwritten with zeros when this is dropped,
+/// and are never shown by `Debug`.
+#[derive(Clone)]
+pub struct AwsCredentials
+{
+	access_key_id: String,
+	secret_access_key: String,
+	session_token: Option<String>,
+}
+
+impl AwsCredentials
+{
+	/// Long-term credentials, such as those of an IAM user
+	pub fn new(access_key_id: impl Into<String>, secret_access_key: impl Into<String>)
+		-> AwsCredentials
+	{
+		AwsCredentials
+		{
+			access_key_id: access_key_id.into(),
+			secret_access_key: secret_access_key.into(),
+			session_token: None,
+		}
+	}
+
+	/// The token that goes with temporary credentials,
+	/// sent in the `X-Amz-Security-Token` header
+	pub fn session_token(mut self, token: impl Into<String>) -> Self
+	{
+		self.session_token = Some(token.into());
+		self
+	}
+}
+
+impl Drop for AwsCredentials
+{
+	fn drop(&mut self)
+	{
+		zeroize(unsafe { self.secret_access_key.as_mut_vec() });
+		if let Some(token) = &mut self.session_token
+			{ zeroize(unsafe { token.as_mut_vec() }); }
+	}
+}
+
+impl std::fmt::Debug for AwsCredentials
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.debug_struct("AwsCredentials")
+			.field("access_key_id", &self.access_key_id)
+			.field("secret_access_key", &"<redacted>")
+			.field("session_token", &self.session_token.as_ref().map(|_| "<redacted>"))
+			.finish()
+	}
+}
+
+impl Auth
+{
+	pub(crate) unsafe fn apply(&self, easy: *mut sys::CURL) -> Result<()>
+	{
+		let (scheme, user) =
+			match self
+			{
+				Auth::Basic { username, password } =>
+					(sys::CURLAUTH_BASIC, Some((username, password))),
+				Auth::Digest { username, password } =>
+					(sys::CURLAUTH_DIGEST, Some((username, password))),
+				Auth::Any { username, password } =>
+					(sys::CURLAUTH_ANY, Some((username, password))),
+				Auth::Bearer(token) =>
+				{
+					let token = Secret::new(token)?;
+					cr(sys::curl_easy_setopt(easy, ffi::CURLOPT_XOAUTH2_BEARER, token.as_ptr()))?;
+					(ffi::CURLAUTH_BEARER, None)
+				},
+				Auth::AwsSigV4 { provider, region, service, credentials } =>
+				{
+					let param = format!("{}:{}:{}", provider, region, service);
+					let param = std::ffi::CString::new(param)
+						.map_err(|_| Error::new(Kind::Curl("an AWS signing parameter contains a nul byte".to_string()), None))?;
+					cr(sys::curl_easy_setopt(easy, sys::CURLOPT_AWS_SIGV4, param.as_ptr()))?;
+					if credentials.session_token.as_ref().is_some_and(|t| HeaderValue::from_str(t).is_err())
+						{ return Err(Error::new(Kind::InvalidHeader(aws_header(provider, "security-token")), None)); }
+					let username = Secret::new(&credentials.access_key_id)?;
+					let password = Secret::new(&credentials.secret_access_key)?;
+					cr(sys::curl_easy_setopt(easy, sys::CURLOPT_USERNAME, username.as_ptr()))?;
+					cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PASSWORD, password.as_ptr()))?;
+					return Ok(());
+				},
+				Auth::Negotiate =>
+				{
+					// the name comes from the ticket, but curl wants one set
+					cr(sys::curl_easy_setopt(easy, sys::CURLOPT_USERNAME, c"".as_ptr()))?;
+					(sys::CURLAUTH_GSSNEGOTIATE, None)
+				},
+			};
+		if let Some((username, password)) = user
+		{
+			let username = Secret::new(username)?;
+			let password = Secret::new(password)?;
+			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_USERNAME, username.as_ptr()))?;
+			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PASSWORD, password.as_ptr()))?;
+		}
+		cr(sys::curl_easy_setopt(easy, sys::CURLOPT_HTTPAUTH, scheme as c_ulong))
+	}
+
+	/// Authenticate to the proxy instead of the server
+	pub(crate) unsafe fn apply_proxy(&self, easy: *mut sys::CURL) -> Result<()>
+	{
+		let (scheme, user) =
+			match self
+			{
+				Auth::Basic { username, password } =>
+					(sys::CURLAUTH_BASIC, Some((username, password))),
+				Auth::Digest { username, password } =>
+					(sys::CURLAUTH_DIGEST, Some((username, password))),
+				Auth::Any { username, password } =>
+					(sys::CURLAUTH_ANY, Some((username, password))),
+				Auth::Negotiate => (sys::CURLAUTH_GSSNEGOTIATE, None),
+				Auth::Bearer(_) | Auth::AwsSigV4 { .. } =>
+					return Err(Error::new(Kind::Curl("a proxy can't use this authentication".to_string()), None)),
+			};
+		match user
+		{
+			Some((username, password)) =>
+			{
+				let username = Secret::new(username)?;
+				let password = Secret::new(password)?;
+				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXYUSERNAME, username.as_ptr()))?;
+				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXYPASSWORD, password.as_ptr()))?;
+			},
+			None => cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXYUSERNAME, c"".as_ptr()))?,
+		}
+		cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXYAUTH, scheme as c_ulong))
+	}
+
+	/// Add the headers that curl doesn't add by itself
+	pub(crate) fn append_headers(&self, list: &mut CurlList, headers: &HeaderMap, sends_body: bool)
+	{
+		if let Auth::AwsSigV4 { provider, credentials, .. } = self
+		{
+			if let Some(token) = &credentials.session_token
+			{
+				let mut h = format!("{}: {}", aws_header(provider, "security-token"), token);
+				list.push(h.as_bytes());
+				zeroize(unsafe { h.as_mut_vec() });
+			}
+			// a body can only be hashed by reading it, so
+			// it is sent unsigned unless the caller knows its hash
+			let hash = aws_header(provider, "content-sha256");
+			if sends_body && !headers.contains_key(hash.as_str())
+				{ list.push(format!("{}: UNSIGNED-PAYLOAD", hash).as_bytes()); }
+		}
+	}
+}
+
+/// The name of one of the `x-amz-` headers, whose prefix comes
+/// from the second part of the provider
+fn aws_header(provider: &str, name: &str) -> String
+{
+	let prefix = provider.split(':').nth(1).unwrap_or(provider);
+	format!("x-{}-{}", prefix.to_ascii_lowercase(), name)
+}
+
+impl Drop for Auth
+{
+	fn drop(&mut self)
+	{
+		match self
+		{
+			Auth::Basic { username, password }
+				| Auth::Digest { username, password }
+				| Auth::Any { username, password } =>
+			{
+				zeroize(unsafe { username.as_mut_vec() });
+				zeroize(unsafe { password.as_mut_vec() });
+			},
+			Auth::Bearer(token) => zeroize(unsafe { token.as_mut_vec() }),
+			Auth::AwsSigV4 { .. } | Auth::Negotiate => {},
+		}
+	}
+}
+
+impl std::fmt::Debug for Auth
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		match self
+		{
+			Auth::Basic { username, .. } =>
+				f.debug_struct("Basic").field("username", username).field("password", &"<redacted>").finish(),
+			Auth::Digest { username, .. } =>
+				f.debug_struct("Digest").field("username", username).field("password", &"<redacted>").finish(),
+			Auth::Any { username, .. } =>
+				f.debug_struct("Any").field("username", username).field("password", &"<redacted>").finish(),
+			Auth::Bearer(_) => f.debug_tuple("Bearer").field(&"<redacted>").finish(),
+			Auth::AwsSigV4 { provider, region, service, credentials } =>
+				f.debug_struct("AwsSigV4")
+					.field("provider", provider)
+					.field("region", region)
+					.field("service", service)
+					.field("credentials", credentials)
+					.finish(),
+			Auth::Negotiate => f.write_str("Negotiate"),
+		}
+	}
+}
+
+/// Overwrite with zeros in a way that the compiler won't remove
+pub(crate) fn zeroize(bytes: &mut [u8])
+{
+	for b in bytes.iter_mut()
+	{
+		unsafe { std::ptr::write_volatile(b, 0); }
+	}
+	std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// A nul-terminated copy of a secret for curl, zeroed on drop
+///
+/// curl makes its own copy, so this only needs to live
+/// until the option is set.
+pub(crate) struct Secret(Vec<u8>);
+
+impl Secret
+{
+	pub(crate) fn new(s: &str) -> Result<Secret>
+	{
+		let mut bytes = Vec::with_capacity(s.len()+1);
+		bytes.extend_from_slice(s.as_bytes());
+		bytes.push(0);
+		let secret = Secret(bytes);
+		if s.contains('\0')
+			{ return Err(Error::new(Kind::Curl("a credential contains a nul byte".to_string()), None)); }
+		Ok(secret)
+	}
+
+	pub(crate) fn as_ptr(&self) -> *const libc::c_char
+	{
+		self.0.as_ptr() as *const libc::c_char
+	}
+}
+
+impl Drop for Secret
+{
+	fn drop(&mut self)
+	{
+		zeroize(&mut self.0);
+	}
+}
@@ -0,0 +1,182 @@
+use std::borrow::Cow;
+use std::io::Read;
+
+/// The payload sent with a request
+///
+/// A `Body` is either a reader, which is streamed as it's read,
+/// or bytes in memory. The length of bytes in memory is known in
+/// advance, and they can be sent again when a redirect requires it.
+pub struct Body<'b>
+{
+	inner: Inner<'b>,
+}
+
+enum Inner<'b>
+{
+	Bytes(Cow<'b, [u8]>, usize),
+	Reader(Box<dyn Read + 'b>),
+}
+
+impl<'b> Body<'b>
+{
+	/// A body with nothing in it
+	pub fn empty() -> Body<'static>
+	{
+		Body::from(Vec::new())
+	}
+
+	/// A body that's read from `r` as it is sent
+	pub fn reader<R>(r: R) -> Body<'b>
+		where R: Read + 'b
+	{
+		Body
+		{
+			inner: Inner::Reader(Box::new(r)),
+		}
+	}
+
+	/// The number of bytes in the body, if it's known
+	pub fn content_length(&self) -> Option<u64>
+	{
+		match &self.inner
+		{
+			Inner::Bytes(b, _) => Some(b.len() as u64),
+			Inner::Reader(_) => None,
+		}
+	}
+
+	/// Start reading from the beginning again, if possible
+	pub(crate) fn rewind(&mut self) -> bool
+	{
+		match &mut self.inner
+		{
+			Inner::Bytes(_, pos) => { *pos = 0; true },
+			Inner::Reader(_) => false,
+		}
+	}
+}
+
+/// How a request body is compressed as it is sent
+///
+/// Each is enabled by the Cargo feature of the same name, and this
+/// type only exists with the `gzip` or `zstd` feature.
+/// See [`Request::compress_body`](struct.Request.html#method.compress_body).
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+#[non_exhaustive]
+pub enum Compression
+{
+	/// `Content-Encoding: gzip`, with the default level
+	#[cfg(feature = "gzip")]
+	Gzip,
+	/// `Content-Encoding: zstd`, with the default level
+	#[cfg(feature = "zstd")]
+	Zstd,
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl Compression
+{
+	/// The `Content-Encoding` that names this compression
+	pub fn content_encoding(self) -> &'static str
+	{
+		match self
+		{
+			#[cfg(feature = "gzip")]
+			Compression::Gzip => "gzip",
+			#[cfg(feature = "zstd")]
+			Compression::Zstd => "zstd",
+		}
+	}
+
+	/// A body that is `body` compressed as it's read
+	///
+	/// The length of the result is not known.
+	pub(crate) fn compress<'b>(self, body: Body<'b>) -> std::io::Result<Body<'b>>
+	{
+		match self
+		{
+			#[cfg(feature = "gzip")]
+			Compression::Gzip =>
+				Ok(Body::reader(flate2::read::GzEncoder::new(body, flate2::Compression::default()))),
+			#[cfg(feature = "zstd")]
+			Compression::Zstd =>
+				Ok(Body::reader(zstd::stream::read::Encoder::new(body, 0)?)),
+		}
+	}
+}
+
+impl Read for Body<'_>
+{
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>
+	{
+		match &mut self.inner
+		{
+			Inner::Bytes(b, pos) =>
+			{
+				let n = (&b[*pos ..]).read(buf)?;
+				*pos += n;
+				Ok(n)
+			},
+			Inner::Reader(r) => r.read(buf),
+		}
+	}
+}
+
+impl From<Vec<u8>> for Body<'static>
+{
+	fn from(b: Vec<u8>) -> Self
+	{
+		Body
+		{
+			inner: Inner::Bytes(Cow::Owned(b), 0),
+		}
+	}
+}
+
+impl From<String> for Body<'static>
+{
+	fn from(s: String) -> Self
+	{
+		Body::from(s.into_bytes())
+	}
+}
+
+impl<'b> From<&'b [u8]> for Body<'b>
+{
+	fn from(b: &'b [u8]) -> Self
+	{
+		Body
+		{
+			inner: Inner::Bytes(Cow::Borrowed(b), 0),
+		}
+	}
+}
+
+impl<'b> From<&'b str> for Body<'b>
+{
+	fn from(s: &'b str) -> Self
+	{
+		Body::from(s.as_bytes())
+	}
+}
+
+impl From<()> for Body<'static>
+{
+	fn from(_: ()) -> Self
+	{
+		Body::empty()
+	}
+}
+
+impl std::fmt::Debug for Body<'_>
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		match self.content_length()
+		{
+			Some(l) => f.write_fmt(format_args!("Body {{length={}}}", l)),
+			None => f.write_str("Body {reader}"),
+		}
+	}
+}
@@ -1,20 +1,517 @@
 use libc::{c_ulong,c_char,size_t,c_void};
 
+use std::io::Read;
+use std::sync::Arc;
+
 use crate::*;
 use crate::header::*;
 
-pub(crate) struct Client
+/// The `User-Agent` a [`Client`] sends unless another is specified
+pub const DEFAULT_USER_AGENT: &str = concat!("idcurl/", env!("CARGO_PKG_VERSION"));
+
+/// Makes requests that share a set of defaults
+///
+/// The defaults are copied into each [`Request`] made with
+/// [`request()`](#method.request), [`get()`](#method.get) and so on,
+/// so that a request may override any of them.
+///
+/// A `Client` is cheap to clone and may be shared between threads.
+/// It does not pool connections: each request is sent over a new
+/// connection, as with [`Request::send()`].
+///
+/// ```no_run
+/// let client = idcurl::Client::new()
+///     .base_url("https://api.example.com/v1/".parse().unwrap())
+///     .default_header(idcurl::header::ACCEPT, "application/json");
+/// let mut response = client.get("users/1")
+///     .send()
+///     .unwrap();
+/// ```
+#[derive(Clone,Debug)]
+pub struct Client
+{
+	config: Arc<ClientConfig>,
+}
+
+#[derive(Clone,Debug)]
+struct ClientConfig
+{
+	headers: HeaderMap,
+	invalid_header: Option<String>,
+	user_agent: Option<String>,
+	base_url: Option<Url>,
+	error_for_status: bool,
+	accept_encoding: bool,
+	tls: TlsConfig,
+	netrc: Netrc,
+	netrc_file: Option<std::path::PathBuf>,
+	token_provider: Option<Arc<dyn TokenProvider>>,
+	proxy: Option<Proxy>,
+	no_proxy: crate::proxy::NoProxy,
+	proxy_selector: Option<crate::proxy::ProxySelector>,
+	unix_sockets: Vec<(String, Proxy)>,
+}
+
+impl Client
+{
+	/// Create a client with no default headers, no base URL
+	/// and the [`DEFAULT_USER_AGENT`]
+	pub fn new() -> Client
+	{
+		Client
+		{
+			config: Arc::new(ClientConfig
+			{
+				headers: HeaderMap::new(),
+				invalid_header: None,
+				user_agent: Some(DEFAULT_USER_AGENT.to_string()),
+				base_url: None,
+				error_for_status: false,
+				accept_encoding: false,
+				tls: TlsConfig::new(),
+				netrc: Netrc::Ignored,
+				netrc_file: None,
+				token_provider: None,
+				proxy: None,
+				no_proxy: Default::default(),
+				proxy_selector: None,
+				unix_sockets: vec!(),
+			}),
+		}
+	}
+
+	fn config(&mut self) -> &mut ClientConfig
+	{
+		Arc::make_mut(&mut self.config)
+	}
+
+	/// Add a header that is sent with every request
+	///
+	/// A header of the same name set on the request replaces it.
+	/// If the name or value is not valid, requests fail with
+	/// [`Kind::InvalidHeader`](enum.Kind.html#variant.InvalidHeader).
+	pub fn set_default_header<K,V>(&mut self, k: K, v: V)
+		where K: AsRef<[u8]>, V: AsRef<[u8]>
+	{
+		let (k,v) = (k.as_ref(), v.as_ref());
+		let config = self.config();
+		match HeaderName::from_bytes(k).ok().zip(HeaderValue::from_bytes(v).ok())
+		{
+			Some((k,v)) => { config.headers.append(k, v); },
+			None =>
+			{
+				config.invalid_header
+					.get_or_insert_with(|| String::from_utf8_lossy(k).into_owned());
+			},
+		}
+	}
+
+	/// Add a header that is sent with every request
+	///
+	/// A header of the same name set on the request replaces it.
+	/// If the name or value is not valid, requests fail with
+	/// [`Kind::InvalidHeader`](enum.Kind.html#variant.InvalidHeader).
+	pub fn default_header<K,V>(mut self, k: K, v: V) -> Self
+		where K: AsRef<[u8]>, V: AsRef<[u8]>
+	{
+		self.set_default_header(k, v);
+		self
+	}
+
+	/// The headers that are sent with every request, for modification
+	pub fn default_headers_mut(&mut self) -> &mut HeaderMap
+	{
+		&mut self.config().headers
+	}
+
+	/// Set the `User-Agent`, or don't send one with `None`
+	///
+	/// The default is [`DEFAULT_USER_AGENT`]. A `User-Agent` header
+	/// set on the request or with [`default_header`](#method.default_header)
+	/// takes precedence.
+	pub fn set_user_agent(&mut self, ua: Option<&str>)
+	{
+		self.config().user_agent = ua.map(|u| u.to_string());
+	}
+
+	/// Set the `User-Agent`, or don't send one with `None`
+	///
+	/// The default is [`DEFAULT_USER_AGENT`]. A `User-Agent` header
+	/// set on the request or with [`default_header`](#method.default_header)
+	/// takes precedence.
+	pub fn user_agent(mut self, ua: Option<&str>) -> Self
+	{
+		self.set_user_agent(ua);
+		self
+	}
+
+	/// Resolve the URLs of requests relative to this one
+	///
+	/// As in a web browser, the base URL should end with a `/`
+	/// when its last segment is a directory: relative to
+	/// `https://example.com/v1/`, `users` is
+	/// `https://example.com/v1/users` but relative to
+	/// `https://example.com/v1` it's `https://example.com/users`.
+	///
+	/// Absolute URLs are not affected.
+	pub fn set_base_url(&mut self, url: Url)
+	{
+		self.config().base_url = Some(url);
+	}
+
+	/// Resolve the URLs of requests relative to this one
+	///
+	/// As in a web browser, the base URL should end with a `/`
+	/// when its last segment is a directory: relative to
+	/// `https://example.com/v1/`, `users` is
+	/// `https://example.com/v1/users` but relative to
+	/// `https://example.com/v1` it's `https://example.com/users`.
+	///
+	/// Absolute URLs are not affected.
+	pub fn base_url(mut self, url: Url) -> Self
+	{
+		self.set_base_url(url);
+		self
+	}
+
+	/// Make requests fail with [`Kind::HttpStatus`](enum.Kind.html#variant.HttpStatus)
+	/// on a 4xx or 5xx status
+	///
+	/// See [`Response::error_for_status`].
+	pub fn set_error_for_status(&mut self, yes: bool)
+	{
+		self.config().error_for_status = yes;
+	}
+
+	/// Make requests fail with [`Kind::HttpStatus`](enum.Kind.html#variant.HttpStatus)
+	/// on a 4xx or 5xx status
+	///
+	/// See [`Response::error_for_status`].
+	pub fn error_for_status(mut self, yes: bool) -> Self
+	{
+		self.set_error_for_status(yes);
+		self
+	}
+
+	/// Ask for compressed responses, which are decompressed as they're read
+	///
+	/// See [`Request::accept_encoding`].
+	pub fn set_accept_encoding(&mut self, yes: bool)
+	{
+		self.config().accept_encoding = yes;
+	}
+
+	/// Ask for compressed responses, which are decompressed as they're read
+	///
+	/// See [`Request::accept_encoding`].
+	pub fn accept_encoding(mut self, yes: bool) -> Self
+	{
+		self.set_accept_encoding(yes);
+		self
+	}
+
+	/// Sets how TLS connections are made and verified
+	pub fn set_tls(&mut self, tls: TlsConfig)
+	{
+		self.config().tls = tls;
+	}
+
+	/// Sets how TLS connections are made and verified
+	pub fn tls(mut self, tls: TlsConfig) -> Self
+	{
+		self.set_tls(tls);
+		self
+	}
+
+	/// Take the username and password from a `.netrc` file
+	///
+	/// See [`Request::netrc`].
+	pub fn set_netrc(&mut self, netrc: Netrc)
+	{
+		self.config().netrc = netrc;
+	}
+
+	/// Take the username and password from a `.netrc` file
+	///
+	/// See [`Request::netrc`].
+	pub fn netrc(mut self, netrc: Netrc) -> Self
+	{
+		self.set_netrc(netrc);
+		self
+	}
+
+	/// Read this file instead of `~/.netrc`
+	pub fn set_netrc_file(&mut self, path: impl AsRef<std::path::Path>)
+	{
+		self.config().netrc_file = Some(path.as_ref().to_path_buf());
+	}
+
+	/// Read this file instead of `~/.netrc`
+	pub fn netrc_file(mut self, path: impl AsRef<std::path::Path>) -> Self
+	{
+		self.set_netrc_file(path);
+		self
+	}
+
+	/// Send a bearer token from this provider with every request
+	///
+	/// A request that has its own [`Auth`] or `Authorization` header
+	/// doesn't use it. If the server answers 401 Unauthorized, the
+	/// request is sent once more with a new token, as long as its
+	/// body can be sent again.
+	pub fn set_token_provider(&mut self, provider: impl TokenProvider + 'static)
+	{
+		self.config().token_provider = Some(Arc::new(provider));
+	}
+
+	/// Send a bearer token from this provider with every request
+	///
+	/// See [`set_token_provider`](#method.set_token_provider).
+	pub fn token_provider(mut self, provider: impl TokenProvider + 'static) -> Self
+	{
+		self.set_token_provider(provider);
+		self
+	}
+
+	/// Send every request via this proxy
+	///
+	/// `None` means [`Proxy::FromEnvironment`], which is the default.
+	pub fn set_proxy(&mut self, proxy: impl Into<Option<Proxy>>)
+	{
+		self.config().proxy = proxy.into();
+	}
+
+	/// Send every request via this proxy
+	///
+	/// `None` means [`Proxy::FromEnvironment`], which is the default.
+	pub fn proxy(mut self, proxy: impl Into<Option<Proxy>>) -> Self
+	{
+		self.set_proxy(proxy);
+		self
+	}
+
+	/// Connect directly to these hosts instead of via a proxy
+	///
+	/// The list is comma-separated, in the format of the `no_proxy`
+	/// environment variable: `*` matches every host, a name
+	/// such as `example.com` or `.example.com` matches that domain
+	/// and its subdomains and an IP address matches itself or, with
+	/// a prefix length such as `10.0.0.0/8`, its network.
+	///
+	/// This applies to any proxy other than a unix socket. With
+	/// [`Proxy::FromEnvironment`], the hosts in `no_proxy` are
+	/// connected to directly as well.
+	pub fn set_no_proxy(&mut self, hosts: &str)
+	{
+		self.config().no_proxy = crate::proxy::NoProxy::new(hosts);
+	}
+
+	/// Connect directly to these hosts instead of via a proxy
+	///
+	/// See [`set_no_proxy`](#method.set_no_proxy).
+	pub fn no_proxy(mut self, hosts: &str) -> Self
+	{
+		self.set_no_proxy(hosts);
+		self
+	}
+
+	/// Choose the proxy for each request and each redirect
+	///
+	/// `selector` is called with the URL, and a proxy it returns
+	/// is used as if it were set with [`proxy`](#method.proxy).
+	/// When it returns `None`, the client's proxy is used.
+	/// [`Request::proxy`] overrides the selector.
+	///
+	/// ```no_run
+	/// use idcurl::Proxy;
+	/// let client = idcurl::Client::new()
+	///     .proxy_selector(
+	///         |url| match url.host()
+	///         {
+	///             Some(h) if h.ends_with(".corp.example.com") =>
+	///                 Some(Proxy::Host("socks5://egress.corp.example.com".to_string())),
+	///             Some("docker") =>
+	///                 Some(Proxy::UnixSocket("/var/run/docker.sock".to_string())),
+	///             _ => Some(Proxy::None),
+	///         }
+	///     );
+	/// ```
+	pub fn set_proxy_selector<F>(&mut self, selector: F)
+		where F: Fn(&Url) -> Option<Proxy> + Send + Sync + 'static
+	{
+		self.config().proxy_selector = Some(crate::proxy::ProxySelector(Arc::new(selector)));
+	}
+
+	/// Choose the proxy for each request and each redirect
+	///
+	/// See [`set_proxy_selector`](#method.set_proxy_selector).
+	pub fn proxy_selector<F>(mut self, selector: F) -> Self
+		where F: Fn(&Url) -> Option<Proxy> + Send + Sync + 'static
+	{
+		self.set_proxy_selector(selector);
+		self
+	}
+
+	/// Connect to `host` via the Unix domain socket at `path`
+	///
+	/// Requests to other hosts are made as usual, so one client
+	/// can talk to both a local daemon and the network:
+	///
+	/// ```no_run
+	/// let client = idcurl::Client::new()
+	///     .unix_socket("docker", "/var/run/docker.sock");
+	/// let mut containers = client.get("http://docker/containers/json")
+	///     .send()
+	///     .unwrap();
+	/// ```
+	///
+	/// The host is matched without its port and takes precedence
+	/// over the [`proxy_selector`](#method.proxy_selector).
+	pub fn set_unix_socket(&mut self, host: &str, path: impl Into<String>)
+	{
+		self.set_host_socket(host, Proxy::UnixSocket(path.into()));
+	}
+
+	/// Connect to `host` via the Unix domain socket at `path`
+	///
+	/// See [`set_unix_socket`](#method.set_unix_socket).
+	pub fn unix_socket(mut self, host: &str, path: impl Into<String>) -> Self
+	{
+		self.set_unix_socket(host, path);
+		self
+	}
+
+	/// Connect to `host` via the Linux abstract Unix domain
+	/// socket `name`, without its leading nul byte
+	///
+	/// See [`set_unix_socket`](#method.set_unix_socket).
+	pub fn set_abstract_unix_socket(&mut self, host: &str, name: impl Into<String>)
+	{
+		self.set_host_socket(host, Proxy::AbstractUnixSocket(name.into()));
+	}
+
+	/// Connect to `host` via the Linux abstract Unix domain
+	/// socket `name`, without its leading nul byte
+	///
+	/// See [`set_unix_socket`](#method.set_unix_socket).
+	pub fn abstract_unix_socket(mut self, host: &str, name: impl Into<String>) -> Self
+	{
+		self.set_abstract_unix_socket(host, name);
+		self
+	}
+
+	fn set_host_socket(&mut self, host: &str, socket: Proxy)
+	{
+		let sockets = &mut self.config().unix_sockets;
+		sockets.retain(|(h, _)| !h.eq_ignore_ascii_case(host));
+		sockets.push((host.to_string(), socket));
+	}
+
+	/// The proxy that a request for `url` would go through,
+	/// which is [`Proxy::None`] for a direct connection
+	///
+	/// ```no_run
+	/// let client = idcurl::Client::new()
+	///     .proxy(idcurl::Proxy::Host("http://proxy.example.com:3128".to_string()))
+	///     .no_proxy("localhost,.internal.example.com");
+	/// let url = "http://db.internal.example.com/".parse().unwrap();
+	/// assert!(matches!(client.proxy_for(&url), idcurl::Proxy::None));
+	/// ```
+	pub fn proxy_for(&self, url: &Url) -> Proxy
+	{
+		Proxy::select(
+			url,
+			&self.config.unix_sockets,
+			self.config.proxy_selector.as_ref(),
+			self.config.proxy.as_ref(),
+			&self.config.no_proxy,
+		)
+	}
+
+	/// Create a request for a specific HTTP method and URL,
+	/// which may be relative to the [`base_url`](#method.base_url)
+	pub fn request(&self, method: Method, url: &str) -> Request<'static>
+	{
+		let config = &self.config;
+		let mut request = Request::new(method, url.to_string());
+		if let Some(base) = &config.base_url
+		{
+			match base.join(url)
+			{
+				Ok(u) => request.url = Some(u.to_string()),
+				Err(e) => request.error = Some(e),
+			}
+		}
+
+		request.headers = config.headers.clone();
+		request.user_agent = config.user_agent.clone();
+		request.error_for_status = config.error_for_status;
+		request.accept_encoding = config.accept_encoding;
+		request.tls = config.tls.clone();
+		request.netrc = config.netrc;
+		request.netrc_file = config.netrc_file.clone();
+		request.token_provider = config.token_provider.clone();
+		request.proxy = config.proxy.clone();
+		request.no_proxy = config.no_proxy.clone();
+		request.proxy_selector = config.proxy_selector.clone();
+		request.unix_sockets = config.unix_sockets.clone();
+		if let Some(name) = &config.invalid_header
+		{
+			request.error.get_or_insert_with(
+				|| Error::new(Kind::InvalidHeader(name.clone()), request.url.clone())
+			);
+		}
+		request
+	}
+
+	/// Create a GET request
+	pub fn get(&self, url: &str) -> Request<'static>
+	{
+		self.request(Method::GET, url)
+	}
+
+	/// Create a POST request
+	pub fn post(&self, url: &str) -> Request<'static>
+	{
+		self.request(Method::POST, url)
+	}
+
+	/// Create a PUT request
+	pub fn put(&self, url: &str) -> Request<'static>
+	{
+		self.request(Method::PUT, url)
+	}
+
+	/// Create a DELETE request
+	pub fn delete(&self, url: &str) -> Request<'static>
+	{
+		self.request(Method::DELETE, url)
+	}
+}
+
+impl Default for Client
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+pub(crate) struct Handle
 {
 	multi: *mut sys::CURLM,
 	pub(crate) easy: *mut sys::CURL,
+	headers: CurlList,
+	proxy_headers: CurlList,
+	mime: Option<crate::multipart::CurlMime>,
 //	data: Rc<RefCell<TranceiverData>>,
 }
 
 /// eventually will let one reuse the same
 /// connection
-impl Client
+impl Handle
 {
-	pub(crate) fn new() -> Client
+	pub(crate) fn new() -> Handle
 	{
 		crate::init();
 		unsafe
@@ -23,16 +520,14 @@ impl Client
 			assert!(!multi.is_null());
 			let easy = sys::curl_easy_init();
 			assert!(!easy.is_null());
-			crm(sys::curl_multi_add_handle(
-				multi,
-				easy,
-			)).expect("adding handle");
-
 
 			Self
 			{
 				multi,
 				easy,
+				headers: CurlList::new(),
+				proxy_headers: CurlList::new(),
+				mime: None,
 			}
 		}
 	}
@@ -42,8 +537,7 @@ impl Client
 	{
 		unsafe
 		{
-			let e = self.execute2(request);
-			e
+			self.execute2(request)
 		}
 	}
 
@@ -51,59 +545,162 @@ impl Client
 		-> Result<Response>
 	{
 		let mut rd = Box::new(ResponseData::new());
+		rd.on_informational = request.on_informational.take();
+
+		let mut url = Url::parse(request.url.as_ref().unwrap())?;
+		let mut hops = 0;
+
+		loop
+		{
+			self.start(&mut request, &url, &mut rd)?;
+
+			loop
+			{
+				let done = self.wait_and_process()?;
+				if done || rd.headers_done || rd.completed
+				{
+					rd.completed = done;
+					break;
+				}
+			}
+			{
+				let mut status: libc::c_long = 0;
+				cr(sys::curl_easy_getinfo(
+					self.easy,
+					sys::CURLINFO_RESPONSE_CODE,
+					&mut status as *mut _
+				))?;
+				rd.status_code = StatusCode::from_u16(status as u16)
+					.map_err(
+						|e|
+							Error::new(
+								Kind::Curl(format!("invalid status code: {}", e)),
+								None
+							)
+					)?;
+			}
+
+			match next_hop(&mut request, &url, &rd, hops)?
+			{
+				Some(next) =>
+				{
+					url = next;
+					hops += 1;
+					rd.restart();
+				},
+				None if retry_with_new_token(&mut request, &rd)? => rd.restart(),
+				None => break,
+			}
+		}
+
+		let response = Response
+		{
+			handle: self,
+			rd,
+			url,
+		};
+
+		Ok(response)
+	}
+
+	/// (re)configure the handle for a request to `url` and begin it
+	unsafe fn start(&mut self, request: &mut Request, url: &Url, rd: &mut ResponseData)
+		-> Result<()>
+	{
 		let easy = self.easy;
 
+		sys::curl_multi_remove_handle(self.multi, easy);
 		sys::curl_easy_reset(easy);
 
-		let url = std::ffi::CString::new(request.url.as_ref().unwrap().as_str())
+		rd.tls_easy =
+			if url.scheme() == "https"
+				{ easy }
+			else
+				{ std::ptr::null_mut() };
+		let proxy = request.proxy_for(url);
+
+		let url = std::ffi::CString::new(url.as_str())
 			.expect("making string");
 
 		cr(sys::curl_easy_setopt(easy, curl_sys::CURLOPT_URL, url.as_ptr()))?;
 
-		if let Some(n) = request.redirect_limit
+		if let Some(ua) = request.user_agent.as_ref()
 		{
-			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_FOLLOWLOCATION, 1 as c_ulong))?;
-			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_MAXREDIRS, n as c_ulong))?;
+			let ua = std::ffi::CString::new(ua.as_str()).expect("making string");
+			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_USERAGENT, ua.as_ptr()))?;
 		}
 
-		match request.proxy.as_ref()
+		request.tls.apply(easy, &crate::tls::SERVER)?;
+		if let Some(auth) = &request.auth
+			{ auth.apply(easy)?; }
+		if request.netrc != Netrc::Ignored
 		{
-			Some(Proxy::Host(host)) =>
+			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_NETRC, request.netrc.curl()))?;
+			if let Some(path) = &request.netrc_file
 			{
-				let proxy_host = std::ffi::CString::new(host.as_str()).expect("making string");
-				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXY, proxy_host.as_ptr()))?;
-			},
-			Some(Proxy::UnixSocket(path)) =>
-			{
-				let proxy_host = std::ffi::CString::new(path.as_str()).expect("making string");
-				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_UNIX_SOCKET_PATH, proxy_host.as_ptr()))?;
-			},
-			None => {},
+				let path = crate::tls::c_path(path)?;
+				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_NETRC_FILE, path.as_ptr()))?;
+			}
 		}
 
-		let m: (&[u8], bool);
-		match request.method
+		if request.accept_encoding
 		{
-			Method::GET => m=(b"GET\0", false),
-			Method::POST => m=(b"POST\0", true),
-			Method::PUT => m=(b"PUT\0", true),
-			Method::DELETE => m=(b"DELETE\0", false),
-			Method::HEAD => m=(b"HEAD\0", false),
-			Method::OPTIONS => m=(b"OPTIONS\0", false),
-			Method::TRACE => m=(b"TRACE\0", false),
+			// an empty string means all that curl supports
+			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_ACCEPT_ENCODING, c"".as_ptr()))?;
 		}
-		if m.1
+		if !request.decode_content
+		{
+			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_HTTP_CONTENT_DECODING, 0 as c_ulong))?;
+		}
+
+		proxy.apply(easy, &mut self.proxy_headers)?;
+
+		let m: &[u8] =
+			match request.method
+			{
+				Method::GET => b"GET\0",
+				Method::POST => b"POST\0",
+				Method::PUT => b"PUT\0",
+				Method::DELETE => b"DELETE\0",
+				Method::HEAD => b"HEAD\0",
+				Method::OPTIONS => b"OPTIONS\0",
+				Method::TRACE => b"TRACE\0",
+				Method::PATCH => b"PATCH\0",
+			};
+		// the reset means curl no longer refers to the last form
+		self.mime = None;
+		if let (true, Some(form)) = (request.method.sends_body(), request.multipart.as_mut())
+		{
+			let mime = form.build(easy)?;
+			cr(sys::curl_easy_setopt(easy, ffi::CURLOPT_MIMEPOST, mime.as_ptr()))?;
+			self.mime = Some(mime);
+		}
+		else if request.method.sends_body()
 		{
 			// we plan to send a body
 			cr(sys::curl_easy_setopt(easy, sys::CURLOPT_UPLOAD, 1 as c_ulong))?;
+			// trailers need the body to be chunked, which curl only
+			// does when it doesn't know the length
+			let length = request.request_body.as_ref()
+				.and_then(|b| b.content_length())
+				.filter(|_| request.trailers.is_none());
+			if let Some(length) = length
+			{
+				cr(sys::curl_easy_setopt(
+					easy,
+					sys::CURLOPT_INFILESIZE_LARGE,
+					length as sys::curl_off_t
+				))?;
+			}
 		}
-		cr(sys::curl_easy_setopt(easy, sys::CURLOPT_CUSTOMREQUEST, m.0.as_ptr()))?;
+		cr(sys::curl_easy_setopt(easy, sys::CURLOPT_CUSTOMREQUEST, m.as_ptr()))?;
 
 
-		cr(sys::curl_easy_setopt(easy, sys::CURLOPT_HTTPHEADER, request.headers.as_ref().unwrap().headers))?;
+		self.headers = request.curl_headers();
+		cr(sys::curl_easy_setopt(easy, sys::CURLOPT_HTTPHEADER, self.headers.headers))?;
 
 		{
-			let rd = &mut request as &mut Request as *mut Request;
+			let rd = request as &mut Request as *mut Request;
 			cr(sys::curl_easy_setopt(
 				easy,
 				sys::CURLOPT_READDATA,
@@ -116,9 +713,22 @@ impl Client
 			read_callback as sys::curl_read_callback
 				as *const sys::curl_read_callback
 		))?;
+		{
+			let rd = request as &mut Request as *mut Request;
+			cr(sys::curl_easy_setopt(
+				easy,
+				sys::CURLOPT_SEEKDATA,
+				rd,
+			))?;
+		}
+		cr(sys::curl_easy_setopt(
+			easy,
+			sys::CURLOPT_SEEKFUNCTION,
+			seek_callback as sys::curl_seek_callback
+		))?;
 
 		{
-			let rd = &mut rd as &mut ResponseData as *mut ResponseData;
+			let rd = rd as &mut ResponseData as *mut ResponseData;
 			cr(sys::curl_easy_setopt(
 				easy,
 				sys::CURLOPT_WRITEDATA,
@@ -133,7 +743,7 @@ impl Client
 		))?;
 
 		{
-			let rd = &mut rd as &mut ResponseData as *mut ResponseData;
+			let rd = rd as &mut ResponseData as *mut ResponseData;
 			cr(sys::curl_easy_setopt(
 				easy,
 				sys::CURLOPT_HEADERDATA,
@@ -147,39 +757,22 @@ impl Client
 				as *const sys::curl_write_callback
 		))?;
 
-		loop
-		{
-			let done = self.wait_and_process()?;
-			if done || rd.headers_done || rd.completed
-			{
-				rd.completed = done;
-				break;
-			}
-		}
+		if request.trailers.is_some()
 		{
-			let mut status: libc::c_long = 0;
-			cr(sys::curl_easy_getinfo(
-				self.easy,
-				sys::CURLINFO_RESPONSE_CODE,
-				&mut status as *mut _
+			let rd = request as &mut Request as *mut Request;
+			cr(sys::curl_easy_setopt(
+				easy,
+				ffi::CURLOPT_TRAILERDATA,
+				rd,
+			))?;
+			cr(sys::curl_easy_setopt(
+				easy,
+				ffi::CURLOPT_TRAILERFUNCTION,
+				trailer_callback as ffi::curl_trailer_callback
 			))?;
-			rd.status_code = StatusCode::from_u16(status as u16)
-				.map_err(
-					|e|
-						Error::new(
-							Kind::Curl(format!("invalid status code: {}", e)),
-							None
-						)
-				)?;
 		}
 
-		let response = Response
-		{
-			client: self,
-			rd,
-		};
-
-		Ok(response)
+		crm(sys::curl_multi_add_handle(self.multi, easy))
 	}
 
 	pub(crate) fn wait_and_process(&mut self) -> Result<bool>
@@ -221,7 +814,7 @@ impl Client
 	}
 }
 
-impl Drop for Client
+impl Drop for Handle
 {
 	fn drop(&mut self)
 	{
@@ -234,6 +827,130 @@ impl Drop for Client
 	}
 }
 
+/// Decide if the response in `rd` is a redirect that should be followed
+/// and if so, prepare `request` for it
+fn next_hop(request: &mut Request, url: &Url, rd: &ResponseData, hops: usize)
+	-> Result<Option<Url>>
+{
+	let status = rd.status_code;
+	if !matches!(status.as_u16(), 301 | 302 | 303 | 307 | 308)
+		{ return Ok(None); }
+
+	let location = rd.headers.get(LOCATION)
+		.and_then(|l| l.to_str().ok());
+	let (location, limit) =
+		match (location, request.redirect_limit)
+		{
+			(Some(l), Some(n)) => (l, n),
+			_ => return Ok(None),
+		};
+	// a Location that isn't a URL isn't followed
+	let to =
+		match url.join(location)
+		{
+			Ok(to) => to,
+			Err(_) => return Ok(None),
+		};
+	let policy = &request.redirect_policy;
+
+	let method =
+		match (status.as_u16(), request.method)
+		{
+			(303, Method::HEAD) => Method::HEAD,
+			(303, _) => Method::GET,
+			(301 | 302, Method::POST) if !policy.keep_post => Method::GET,
+			(_, m) => m,
+		};
+
+	if !can_resend(request, method)
+		{ return Ok(None); }
+
+	let redirect = Redirect
+	{
+		status,
+		headers: &rd.headers,
+		from: url,
+		to: &to,
+		method,
+		hops,
+	};
+	if !policy.allows(&redirect)
+		{ return Ok(None); }
+	// a redirect the policy refuses is returned even at the limit
+	if hops >= limit
+		{ return Err(Error::new(Kind::TooManyRedirects, Some(url.to_string()))); }
+
+	if !policy.forward_authorization && !url.same_origin(&to)
+	{
+		request.headers.remove(AUTHORIZATION);
+		request.headers.remove(COOKIE);
+		request.auth = None;
+	}
+	if !request.proxy_for(url).same_as(&request.proxy_for(&to))
+		{ request.headers.remove(PROXY_AUTHORIZATION); }
+	if !method.sends_body()
+	{
+		request.request_body = None;
+		request.multipart = None;
+		request.trailers = None;
+		for name in [CONTENT_LENGTH, CONTENT_TYPE, CONTENT_ENCODING, TRANSFER_ENCODING]
+			{ request.headers.remove(name); }
+	}
+	else
+	{
+		rewind(request);
+	}
+	request.method = method;
+
+	Ok(Some(to))
+}
+
+/// Whether the body can be sent again with `method`
+fn can_resend(request: &Request, method: Method) -> bool
+{
+	if !method.sends_body()
+		{ return true; }
+	// a body that has already been read can only be sent again
+	// if it can be rewound, and a form's readers can't be
+	if request.body_read && request.request_body.as_ref().is_none_or(|b| b.content_length().is_none())
+		{ return false; }
+	request.multipart.as_ref().is_none_or(|m| m.can_resend())
+}
+
+fn rewind(request: &mut Request)
+{
+	if request.method.sends_body() && request.body_read
+	{
+		request.request_body.as_mut().unwrap().rewind();
+		request.body_read = false;
+	}
+}
+
+/// Get a new token and send the request again, once, if
+/// the server rejected the token provider's token
+fn retry_with_new_token(request: &mut Request, rd: &ResponseData)
+	-> Result<bool>
+{
+	if rd.status_code != StatusCode::UNAUTHORIZED || !can_resend(request, request.method)
+		{ return Ok(false); }
+	// not if the token was dropped on a redirect to another origin
+	let rejected = request.headers.get(AUTHORIZATION)
+		.and_then(|v| v.to_str().ok())
+		.and_then(|v| v.strip_prefix("Bearer "))
+		.map(|t| t.to_string());
+	let (provider, rejected) =
+		match (request.token_provider.take(), rejected)
+		{
+			(Some(p), Some(r)) => (p, r),
+			_ => return Ok(false),
+		};
+
+	provider.invalidate(&rejected);
+	request.set_bearer_token(&provider.token()?)?;
+	rewind(request);
+	Ok(true)
+}
+
 extern "C" fn write_callback(
 	bytes: *mut c_char,
 	size: size_t,
@@ -273,6 +990,7 @@ extern "C" fn read_callback(
 		{
 			Some(b) =>
 			{
+				request.body_read = true;
 				let e = b.read(buf);
 				if let Ok(e) = e
 					{ e as size_t }
@@ -285,6 +1003,67 @@ extern "C" fn read_callback(
 	}
 }
 
+extern "C" fn seek_callback(
+	data: *mut c_void,
+	offset: sys::curl_off_t,
+	origin: libc::c_int
+) -> libc::c_int
+{
+	unsafe
+	{
+		let request = data as *mut Request;
+		let request = &mut *request;
+
+		// curl only ever rewinds to the start
+		if offset != 0 || origin != libc::SEEK_SET
+			{ return sys::CURL_SEEKFUNC_CANTSEEK; }
+		if !request.body_read
+			{ return sys::CURL_SEEKFUNC_OK; }
+
+		if request.request_body.as_mut().is_some_and(|b| b.rewind())
+		{
+			request.body_read = false;
+			sys::CURL_SEEKFUNC_OK
+		}
+		else
+		{
+			sys::CURL_SEEKFUNC_CANTSEEK
+		}
+	}
+}
+
+extern "C" fn trailer_callback(
+	list: *mut *mut sys::curl_slist,
+	data: *mut c_void
+) -> libc::c_int
+{
+	unsafe
+	{
+		let request = data as *mut Request;
+		let request = &mut *request;
+
+		let trailers =
+			match request.trailers.as_mut()
+			{
+				Some(f) => f(),
+				None => return ffi::CURL_TRAILERFUNC_OK,
+			};
+		let mut l = CurlList::new();
+		for (name, value) in &trailers
+		{
+			let mut h = name.as_str().as_bytes().to_vec();
+			h.extend_from_slice(b": ");
+			h.extend_from_slice(value.as_bytes());
+			l.push(&h);
+		}
+
+		// curl frees the list
+		*list = l.headers;
+		std::mem::forget(l);
+	}
+	ffi::CURL_TRAILERFUNC_OK
+}
+
 extern "C" fn header_callback(
 	bytes: *mut c_char,
 	size: size_t,
@@ -298,6 +1077,47 @@ extern "C" fn header_callback(
 		let response = data as *mut ResponseData;
 		let response = &mut *response;
 
+		if buf.starts_with(b"HTTP/")
+		{
+			// a status line begins a new response, which replaces
+			// the headers of the previous one (a redirect or 1xx)
+			response.header_status = buf.split(|&b| b == b' ')
+				.nth(1)
+				.and_then(|c| StatusCode::from_bytes(c).ok());
+			response.headers.clear();
+			response.in_trailers = false;
+			return size*nmemb;
+		}
+
+		if buf == b"\r\n" || buf == b"\n"
+		{
+			let status = response.header_status
+				.filter(|s| s.is_informational());
+			if let Some(status) = status
+			{
+				let info = Informational
+				{
+					status,
+					headers: std::mem::take(&mut response.headers),
+				};
+				if let Some(cb) = response.on_informational.as_mut()
+					{ cb(&info); }
+				response.informational.push(info);
+			}
+			else
+			{
+				response.in_trailers = true;
+				// the session is only available while the connection is in use
+				if !response.tls_easy.is_null()
+				{
+					#[cfg(feature = "tls-session")]
+					{ response.tls_session = crate::tls::session(response.tls_easy); }
+					response.peer_certificates = crate::tls::peer_certificates(response.tls_easy);
+				}
+			}
+			return size*nmemb;
+		}
+
 		let colon = buf.iter().enumerate()
 			.find_map(|(idx,&b)| if b == b':' { Some(idx) } else { None });
 		if colon.is_none() { return size*nmemb; }
@@ -318,7 +1138,12 @@ extern "C" fn header_callback(
 		let value = HeaderValue::from_bytes(value);
 		if value.is_err() { return 0; }
 
-		response.headers.append(name.unwrap(), value.unwrap());
+		let headers =
+			if response.in_trailers
+				{ &mut response.trailers }
+			else
+				{ &mut response.headers };
+		headers.append(name.unwrap(), value.unwrap());
 	}
 	size*nmemb
 }
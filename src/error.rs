@@ -4,6 +4,7 @@ use crate::*;
 
 /// Specifies the type of error
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Kind
 {
 	/// failure resolving proxy
@@ -36,6 +37,8 @@ pub enum Kind
 	SslCertificate,
 	/// The remote server did not close via SSL
 	SslShutdownFailed,
+	/// The remote server's public key is not the one that was pinned
+	SslPinnedKeyMismatch,
 	/// unknown CURL error
 	Curl(String),
 	/// The expected was not the reported size
@@ -44,6 +47,42 @@ pub enum Kind
 	Timeout,
 	/// The contents were not valid UTF-8
 	NotUtf8(std::string::FromUtf8Error),
+	/// The URL could not be parsed
+	InvalidUrl,
+	/// The `charset` of the response is not one that is known
+	UnknownCharset(String),
+	/// The contents were not valid in this encoding
+	InvalidText(String),
+	/// The request header with this name, or its value, is invalid
+	InvalidHeader(String),
+	/// This HTTP method can't be used
+	UnsupportedMethod(String),
+	/// The response's `Content-Type` is not `multipart/*` with a boundary
+	NotMultipart,
+	/// The `multipart/*` response body is malformed
+	InvalidMultipart,
+	/// The server responded with a 4xx or 5xx status
+	///
+	/// `body` is at most the first
+	/// [`ERROR_BODY_LIMIT`](constant.ERROR_BODY_LIMIT.html)
+	/// bytes of the response.
+	HttpStatus
+	{
+		status: StatusCode,
+		headers: Box<crate::header::HeaderMap>,
+		body: Vec<u8>,
+	},
+	/// The request body could not be serialized as JSON
+	#[cfg(feature = "json")]
+	JsonEncode(serde_json::Error),
+	/// The response body with this status could not be
+	/// deserialized from JSON
+	#[cfg(feature = "json")]
+	JsonDecode
+	{
+		status: StatusCode,
+		error: serde_json::Error,
+	},
 }
 
 #[derive(Debug)]
@@ -70,6 +109,21 @@ impl Error
 		&self.kind
 	}
 
+	pub(crate) fn into_kind(self) -> Kind
+	{
+		self.kind
+	}
+
+	/// returns the HTTP status if the error is because of it
+	pub fn status(&self) -> Option<StatusCode>
+	{
+		match &self.kind
+		{
+			Kind::HttpStatus{ status, .. } => Some(*status),
+			_ => None,
+		}
+	}
+
 	/// returns true if the error is due to network failures
 	pub fn is_network(&self) -> bool
 	{
@@ -112,10 +166,28 @@ impl std::fmt::Display for Error
 				Kind::SslCipher => "The SSL cipher is invalid",
 				Kind::SslCertificate => "Remote server's SSL certificate is invalid",
 				Kind::SslShutdownFailed => "The remote server did not securely close its socket over SSL",
-				Kind::Curl(a) => &a,
+				Kind::SslPinnedKeyMismatch => "The remote server's public key is not the pinned key",
+				Kind::Curl(a) => a,
 				Kind::PartialFile => "The expected was not the reported size",
 				Kind::Timeout => "The specified timeout was exceeded",
 				Kind::NotUtf8(_) => "The contents were not UTF-8",
+				Kind::InvalidUrl => "The URL could not be parsed",
+				Kind::UnknownCharset(c) =>
+					return f.write_fmt(format_args!("The charset {:?} is not known", c)),
+				Kind::InvalidText(e) =>
+					return f.write_fmt(format_args!("The contents were not valid {}", e)),
+				Kind::InvalidHeader(_) => "A request header is invalid",
+				Kind::UnsupportedMethod(_) => "The HTTP method is not supported",
+				Kind::NotMultipart => "The response is not multipart",
+				Kind::InvalidMultipart => "The multipart response is malformed",
+				Kind::HttpStatus{ status, .. } =>
+					return f.write_fmt(format_args!("The server responded with {}", status)),
+				#[cfg(feature = "json")]
+				Kind::JsonEncode(e) =>
+					return f.write_fmt(format_args!("The body could not be encoded as JSON: {}", e)),
+				#[cfg(feature = "json")]
+				Kind::JsonDecode{ status, error } =>
+					return f.write_fmt(format_args!("The response ({}) is not the expected JSON: {}", status, error)),
 			};
 		std::fmt::Display::fmt(msg, f)
 	}
@@ -125,7 +197,13 @@ impl StdError for Error
 {
 	fn source(&self) -> Option<&(dyn StdError + 'static)>
 	{
-		None
+		match &self.kind
+		{
+			Kind::NotUtf8(e) => Some(e),
+			#[cfg(feature = "json")]
+			Kind::JsonEncode(e) | Kind::JsonDecode{ error: e, .. } => Some(e),
+			_ => None,
+		}
 	}
 }
 
@@ -149,7 +227,9 @@ pub(crate) fn kind_from_curl(c: sys::CURLcode) -> Kind
 		sys::CURLE_SSL_CIPHER => Kind::SslCipher,
 		sys::CURLE_SSL_CACERT => Kind::SslCertificate,
 		sys::CURLE_SSL_SHUTDOWN_FAILED => Kind::SslShutdownFailed,
+		sys::CURLE_SSL_PINNEDPUBKEYNOTMATCH => Kind::SslPinnedKeyMismatch,
 		sys::CURLE_OPERATION_TIMEDOUT => Kind::Timeout,
+		sys::CURLE_URL_MALFORMAT => Kind::InvalidUrl,
 		a => Kind::Curl(format!("curl error {}", a)),
 	}
 }
@@ -0,0 +1,119 @@
+//! Parts of libcurl's API that curl-sys does not declare
+
+#![allow(non_camel_case_types,clippy::upper_case_acronyms)]
+
+use libc::c_int;
+
+use crate::sys::*;
+
+pub(crate) enum CURLU {}
+
+extern "C"
+{
+	pub(crate) fn curl_url() -> *mut CURLU;
+	pub(crate) fn curl_url_cleanup(handle: *mut CURLU);
+	pub(crate) fn curl_url_set(
+		handle: *mut CURLU,
+		what: libc::c_uint,
+		part: *const libc::c_char,
+		flags: libc::c_uint,
+	) -> c_int;
+	pub(crate) fn curl_url_get(
+		handle: *mut CURLU,
+		what: libc::c_uint,
+		part: *mut *mut libc::c_char,
+		flags: libc::c_uint,
+	) -> c_int;
+}
+
+pub(crate) const CURLUE_OK: c_int = 0;
+
+pub(crate) const CURLUPART_URL: libc::c_uint = 0;
+pub(crate) const CURLUPART_SCHEME: libc::c_uint = 1;
+pub(crate) const CURLUPART_HOST: libc::c_uint = 5;
+pub(crate) const CURLUPART_PORT: libc::c_uint = 6;
+pub(crate) const CURLUPART_PATH: libc::c_uint = 7;
+pub(crate) const CURLUPART_QUERY: libc::c_uint = 8;
+
+pub(crate) const CURLU_DEFAULT_PORT: libc::c_uint = 1 << 0;
+pub(crate) const CURLU_NON_SUPPORT_SCHEME: libc::c_uint = 1 << 3;
+pub(crate) const CURLU_GUESS_SCHEME: libc::c_uint = 1 << 9;
+
+pub(crate) const CURLOPT_TRAILERFUNCTION: CURLoption = CURLOPTTYPE_FUNCTIONPOINT + 283;
+pub(crate) const CURLOPT_TRAILERDATA: CURLoption = CURLOPTTYPE_OBJECTPOINT + 284;
+
+pub(crate) const CURL_TRAILERFUNC_OK: c_int = 0;
+
+pub(crate) type curl_trailer_callback = extern "C" fn(
+	*mut *mut curl_slist,
+	*mut libc::c_void,
+) -> c_int;
+
+pub(crate) const CURLINFO_HTTP_VERSION: CURLINFO = CURLINFO_LONG + 46;
+
+pub(crate) const CURLOPT_MIMEPOST: CURLoption = CURLOPTTYPE_OBJECTPOINT + 269;
+
+pub(crate) enum curl_mime {}
+pub(crate) enum curl_mimepart {}
+
+pub(crate) type curl_free_callback = extern "C" fn(*mut libc::c_void);
+
+extern "C"
+{
+	pub(crate) fn curl_mime_init(easy: *mut CURL) -> *mut curl_mime;
+	pub(crate) fn curl_mime_free(mime: *mut curl_mime);
+	pub(crate) fn curl_mime_addpart(mime: *mut curl_mime) -> *mut curl_mimepart;
+	pub(crate) fn curl_mime_name(part: *mut curl_mimepart, name: *const libc::c_char)
+		-> CURLcode;
+	pub(crate) fn curl_mime_filename(part: *mut curl_mimepart, filename: *const libc::c_char)
+		-> CURLcode;
+	pub(crate) fn curl_mime_type(part: *mut curl_mimepart, mimetype: *const libc::c_char)
+		-> CURLcode;
+	pub(crate) fn curl_mime_data(
+		part: *mut curl_mimepart,
+		data: *const libc::c_char,
+		datasize: libc::size_t,
+	) -> CURLcode;
+	pub(crate) fn curl_mime_filedata(part: *mut curl_mimepart, filename: *const libc::c_char)
+		-> CURLcode;
+	pub(crate) fn curl_mime_data_cb(
+		part: *mut curl_mimepart,
+		datasize: curl_off_t,
+		readfunc: curl_read_callback,
+		seekfunc: Option<curl_seek_callback>,
+		freefunc: Option<curl_free_callback>,
+		arg: *mut libc::c_void,
+	) -> CURLcode;
+	pub(crate) fn curl_mime_headers(
+		part: *mut curl_mimepart,
+		headers: *mut curl_slist,
+		take_ownership: c_int,
+	) -> CURLcode;
+}
+
+pub(crate) const CURLOPT_TLS13_CIPHERS: CURLoption = CURLOPTTYPE_OBJECTPOINT + 276;
+
+/// `CURL_SSLVERSION_MAX_*` is the version shifted by this much
+pub(crate) const CURL_SSLVERSION_MAX_SHIFT: libc::c_long = 16;
+
+#[cfg(feature = "tls-session")]
+pub(crate) const CURLINFO_TLS_SSL_PTR: CURLINFO = CURLINFO_SLIST + 45;
+
+#[cfg(feature = "tls-session")]
+pub(crate) const CURLSSLBACKEND_OPENSSL: c_int = 1;
+
+#[cfg(feature = "tls-session")]
+#[repr(C)]
+pub(crate) struct curl_tlssessioninfo
+{
+	pub(crate) backend: c_int,
+	pub(crate) internals: *mut libc::c_void,
+}
+
+pub(crate) const CURLOPT_XOAUTH2_BEARER: CURLoption = CURLOPTTYPE_OBJECTPOINT + 220;
+pub(crate) const CURLAUTH_BEARER: libc::c_ulong = 1 << 6;
+
+pub(crate) const CURLOPT_PROXY_PINNEDPUBLICKEY: CURLoption = CURLOPTTYPE_OBJECTPOINT + 263;
+pub(crate) const CURLOPT_PROXY_TLS13_CIPHERS: CURLoption = CURLOPTTYPE_OBJECTPOINT + 277;
+pub(crate) const CURLOPT_HEADEROPT: CURLoption = CURLOPTTYPE_LONG + 229;
+pub(crate) const CURLHEADER_SEPARATE: libc::c_long = 1;
@@ -27,21 +27,43 @@
 //!     .expect("reading response");
 //! ```
 
+mod body;
 mod client;
 mod request;
 mod response;
 mod method;
 mod error;
+mod redirect;
+mod multipart;
+mod multipart_reader;
+mod tls;
+mod auth;
+mod oauth2;
+mod proxy;
+mod url;
+mod ffi;
 
 pub mod header
 {
 	pub use http::header::*;
 }
 
+pub use body::*;
 pub use error::*;
 pub use request::*;
 pub use response::*;
 pub use method::*;
+pub use redirect::*;
+pub use multipart::{Multipart,Part};
+pub use multipart_reader::{MultipartReader,BodyPart};
+pub use tls::{TlsConfig,TlsVersion,CertificateFormat,PeerCertificate};
+pub use auth::{Auth,AwsCredentials,Netrc};
+pub use oauth2::TokenProvider;
+#[cfg(feature = "oauth2")]
+pub use oauth2::ClientCredentials;
+pub use proxy::{Proxy,ProxyServer};
+pub use url::Url;
+pub use client::{Client,DEFAULT_USER_AGENT};
 
 pub type Result<T> = std::result::Result<T, Error>;
 use std::sync::{Once};
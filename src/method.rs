@@ -1,6 +1,8 @@
+use crate::*;
 
 /// Specify the HTTP method to use
-#[derive(Debug,Copy,Clone)]
+#[derive(Debug,Copy,Clone,PartialEq,Eq)]
+#[non_exhaustive]
 pub enum Method
 {
 	GET,
@@ -10,5 +12,56 @@ pub enum Method
 	HEAD,
 	OPTIONS,
 	TRACE,
+	PATCH,
 }
 
+
+impl Method
+{
+	/// does a request with this method have a body
+	pub(crate) fn sends_body(self) -> bool
+	{
+		matches!(self, Method::POST | Method::PUT | Method::PATCH)
+	}
+}
+
+impl From<Method> for http::Method
+{
+	fn from(m: Method) -> http::Method
+	{
+		match m
+		{
+			Method::GET => http::Method::GET,
+			Method::POST => http::Method::POST,
+			Method::PUT => http::Method::PUT,
+			Method::DELETE => http::Method::DELETE,
+			Method::HEAD => http::Method::HEAD,
+			Method::OPTIONS => http::Method::OPTIONS,
+			Method::TRACE => http::Method::TRACE,
+			Method::PATCH => http::Method::PATCH,
+		}
+	}
+}
+
+impl TryFrom<&http::Method> for Method
+{
+	type Error = Error;
+
+	/// Fails with [`Kind::UnsupportedMethod`] for `CONNECT`
+	/// and extension methods
+	fn try_from(m: &http::Method) -> Result<Method>
+	{
+		Ok(match *m
+		{
+			http::Method::GET => Method::GET,
+			http::Method::POST => Method::POST,
+			http::Method::PUT => Method::PUT,
+			http::Method::DELETE => Method::DELETE,
+			http::Method::HEAD => Method::HEAD,
+			http::Method::OPTIONS => Method::OPTIONS,
+			http::Method::TRACE => Method::TRACE,
+			http::Method::PATCH => Method::PATCH,
+			_ => return Err(Error::new(Kind::UnsupportedMethod(m.to_string()), None)),
+		})
+	}
+}
@@ -0,0 +1,337 @@
+use libc::{c_char,c_void,size_t};
+
+use std::borrow::Cow;
+use std::ffi::CString;
+use std::io::Read;
+use std::path::Path;
+
+use crate::*;
+use crate::client::cr;
+use crate::header::*;
+use crate::request::CurlList;
+
+/// A `multipart/form-data` body, as sent by HTML forms with file uploads
+///
+/// It's sent with [`Request::multipart`], using
+/// [curl's MIME API](https://curl.se/libcurl/c/curl_mime_init.html),
+/// which also chooses the boundary and sets the `Content-Type`.
+///
+/// ```no_run
+/// let form = idcurl::Multipart::new()
+///     .text("title", "Holiday")
+///     .file("photo", "beach.jpg").unwrap()
+///     .part(
+///         "notes",
+///         idcurl::Part::bytes(&b"sunny"[..])
+///             .file_name("notes.txt")
+///             .content_type("text/plain")
+///     );
+/// let response = idcurl::Request::post("https://example.com/upload".to_string())
+///     .multipart(form)
+///     .send()
+///     .unwrap();
+/// ```
+#[derive(Debug,Default)]
+pub struct Multipart<'b>
+{
+	parts: Vec<(String, Part<'b>)>,
+}
+
+/// One field of a [`Multipart`]
+pub struct Part<'b>
+{
+	data: PartData<'b>,
+	file_name: Option<String>,
+	content_type: Option<String>,
+	headers: HeaderMap,
+	invalid_header: Option<String>,
+}
+
+enum PartData<'b>
+{
+	Bytes(Cow<'b, [u8]>),
+	File(CString),
+	Reader(PartReader<'b>),
+}
+
+struct PartReader<'b>
+{
+	reader: Box<dyn Read + 'b>,
+	length: Option<u64>,
+	/// some of it was sent, so it can't be sent again
+	read: bool,
+}
+
+impl<'b> Multipart<'b>
+{
+	/// A form with no fields
+	pub fn new() -> Multipart<'b>
+	{
+		Multipart { parts: vec!() }
+	}
+
+	/// Add a field
+	pub fn part<N>(mut self, name: N, part: Part<'b>) -> Self
+		where N: Into<String>
+	{
+		self.parts.push((name.into(), part));
+		self
+	}
+
+	/// Add a text field
+	pub fn text<N, V>(self, name: N, value: V) -> Self
+		where N: Into<String>, V: Into<String>
+	{
+		self.part(name, Part::text(value))
+	}
+
+	/// Add a file which is read as it is sent
+	///
+	/// See [`Part::file`].
+	pub fn file<N, P>(self, name: N, path: P) -> std::io::Result<Self>
+		where N: Into<String>, P: AsRef<Path>
+	{
+		Ok(self.part(name, Part::file(path)?))
+	}
+
+	/// The name of the first header that could not be parsed
+	pub(crate) fn invalid_header(&self) -> Option<&str>
+	{
+		self.parts.iter()
+			.find_map(|(_, p)| p.invalid_header.as_deref())
+	}
+
+	/// Returns true if every part can be sent again
+	pub(crate) fn can_resend(&self) -> bool
+	{
+		self.parts.iter()
+			.all(|(_, p)| !matches!(p.data, PartData::Reader(PartReader { read: true, .. })))
+	}
+
+	/// Build the curl representation of this form, for `easy`
+	///
+	/// The returned handle reads from the `Read` parts, so
+	/// `self` must outlive it.
+	pub(crate) unsafe fn build(&mut self, easy: *mut sys::CURL)
+		-> Result<CurlMime>
+	{
+		let mime = CurlMime(ffi::curl_mime_init(easy));
+		assert!(!mime.0.is_null());
+
+		for (name, p) in &mut self.parts
+		{
+			let part = ffi::curl_mime_addpart(mime.0);
+			assert!(!part.is_null());
+
+			cr(ffi::curl_mime_name(part, c_string(name)?.as_ptr()))?;
+
+			match &mut p.data
+			{
+				PartData::Bytes(b) =>
+					cr(ffi::curl_mime_data(part, b.as_ptr() as *const c_char, b.len() as size_t))?,
+				PartData::File(path) =>
+					cr(ffi::curl_mime_filedata(part, path.as_ptr()))?,
+				PartData::Reader(r) =>
+				{
+					let length = r.length;
+					let r = r as *mut PartReader<'b>;
+					cr(ffi::curl_mime_data_cb(
+						part,
+						length.map(|l| l as sys::curl_off_t).unwrap_or(-1),
+						read_callback,
+						None,
+						None,
+						r as *mut c_void,
+					))?;
+				},
+			}
+
+			// after the data, because curl_mime_filedata sets the filename too
+			if let Some(f) = &p.file_name
+				{ cr(ffi::curl_mime_filename(part, c_string(f)?.as_ptr()))?; }
+			if let Some(t) = &p.content_type
+				{ cr(ffi::curl_mime_type(part, c_string(t)?.as_ptr()))?; }
+			if !p.headers.is_empty()
+			{
+				let mut headers = CurlList::new();
+				headers.append_headers(&p.headers);
+				cr(ffi::curl_mime_headers(part, headers.headers, 1))?;
+				// curl owns the list now
+				headers.headers = std::ptr::null_mut();
+			}
+		}
+		Ok(mime)
+	}
+}
+
+fn c_string(s: &str) -> Result<CString>
+{
+	CString::new(s)
+		.map_err(|_| Error::new(Kind::InvalidHeader(s.to_string()), None))
+}
+
+impl<'b> Part<'b>
+{
+	fn new(data: PartData<'b>) -> Part<'b>
+	{
+		Part
+		{
+			data,
+			file_name: None,
+			content_type: None,
+			headers: HeaderMap::new(),
+			invalid_header: None,
+		}
+	}
+
+	/// A part containing this text
+	pub fn text<V>(value: V) -> Part<'static>
+		where V: Into<String>
+	{
+		Part::new(PartData::Bytes(Cow::Owned(value.into().into_bytes())))
+	}
+
+	/// A part containing these bytes
+	///
+	/// Both `Vec<u8>` and `&[u8]` may be given.
+	pub fn bytes<B>(bytes: B) -> Part<'b>
+		where B: Into<Cow<'b, [u8]>>
+	{
+		Part::new(PartData::Bytes(bytes.into()))
+	}
+
+	/// A part containing the file at `path`
+	///
+	/// The file is read as it is sent. Its name, without the directory,
+	/// is sent as the file name and curl guesses its content type
+	/// from the extension. Fails if the file can't be accessed now.
+	pub fn file<P>(path: P) -> std::io::Result<Part<'static>>
+		where P: AsRef<Path>
+	{
+		let path = path.as_ref();
+		std::fs::metadata(path)?;
+
+		#[cfg(unix)]
+		let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+		#[cfg(not(unix))]
+		let bytes = path.to_string_lossy().into_owned().into_bytes();
+
+		let path = CString::new(bytes)
+			.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+		Ok(Part::new(PartData::File(path)))
+	}
+
+	/// A part that is read from `r` as it is sent
+	///
+	/// Because the length is not known, the request body is sent
+	/// with chunked encoding, and it is not sent again on a redirect.
+	pub fn reader<R>(r: R) -> Part<'b>
+		where R: Read + 'b
+	{
+		Part::new(PartData::Reader(PartReader { reader: Box::new(r), length: None, read: false }))
+	}
+
+	/// A part that is read from `r` as it is sent, which is exactly
+	/// `length` bytes long
+	pub fn reader_with_length<R>(r: R, length: u64) -> Part<'b>
+		where R: Read + 'b
+	{
+		Part::new(PartData::Reader(PartReader { reader: Box::new(r), length: Some(length), read: false }))
+	}
+
+	/// Send this file name in the part's `Content-Disposition`
+	pub fn file_name<F>(mut self, name: F) -> Self
+		where F: Into<String>
+	{
+		self.file_name = Some(name.into());
+		self
+	}
+
+	/// Set the part's `Content-Type`
+	pub fn content_type<T>(mut self, content_type: T) -> Self
+		where T: Into<String>
+	{
+		self.content_type = Some(content_type.into());
+		self
+	}
+
+	/// Add a header to the part
+	///
+	/// If the name or value is not valid, [`Request::send`]
+	/// fails with [`Kind::InvalidHeader`](enum.Kind.html#variant.InvalidHeader).
+	pub fn header<K, V>(mut self, k: K, v: V) -> Self
+		where K: AsRef<[u8]>, V: AsRef<[u8]>
+	{
+		let (k, v) = (k.as_ref(), v.as_ref());
+		match HeaderName::from_bytes(k).ok().zip(HeaderValue::from_bytes(v).ok())
+		{
+			Some((k, v)) => { self.headers.append(k, v); },
+			None =>
+			{
+				self.invalid_header.get_or_insert_with(
+					|| String::from_utf8_lossy(k).into_owned()
+				);
+			},
+		}
+		self
+	}
+}
+
+impl std::fmt::Debug for Part<'_>
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		let data =
+			match &self.data
+			{
+				PartData::Bytes(b) => format!("bytes({})", b.len()),
+				PartData::File(p) => format!("file({:?})", p),
+				PartData::Reader(..) => "reader".to_string(),
+			};
+		f.debug_struct("Part")
+			.field("data", &data)
+			.field("file_name", &self.file_name)
+			.field("content_type", &self.content_type)
+			.field("headers", &self.headers)
+			.finish()
+	}
+}
+
+/// Frees the curl form when dropped
+pub(crate) struct CurlMime(*mut ffi::curl_mime);
+
+impl CurlMime
+{
+	pub(crate) fn as_ptr(&self) -> *mut ffi::curl_mime
+	{
+		self.0
+	}
+}
+
+impl Drop for CurlMime
+{
+	fn drop(&mut self)
+	{
+		unsafe { ffi::curl_mime_free(self.0); }
+	}
+}
+
+extern "C" fn read_callback(
+	bytes: *mut c_char,
+	size: size_t,
+	nmemb: size_t,
+	data: *mut c_void
+) -> size_t
+{
+	unsafe
+	{
+		let buf = std::slice::from_raw_parts_mut(bytes as *mut u8, size*nmemb);
+		let r = &mut *(data as *mut PartReader);
+		r.read = true;
+		match r.reader.read(buf)
+		{
+			Ok(n) => n as size_t,
+			Err(_) => sys::CURL_READFUNC_ABORT,
+		}
+	}
+}
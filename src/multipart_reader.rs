@@ -0,0 +1,280 @@
+use std::io::Read;
+
+use crate::*;
+use crate::header::*;
+use crate::response::content_type_param;
+
+/// The most bytes of headers that a part may have
+const HEADER_LIMIT: usize = 64*1024;
+
+/// Reads the parts of a `multipart/*` response as they are received
+///
+/// This is returned by [`Response::multipart`]. Servers use these
+/// bodies, for example `multipart/mixed` or `multipart/byteranges`,
+/// to answer batch requests or requests for several ranges.
+///
+/// Only a little more than the boundary is buffered, so a part
+/// may be as large as you like.
+///
+/// ```no_run
+/// let mut parts = idcurl::Request::get("https://example.com/file".to_string())
+///     .header("Range", "bytes=0-99,200-299")
+///     .send()
+///     .unwrap()
+///     .multipart()
+///     .unwrap();
+/// while let Some(mut part) = parts.next_part().unwrap()
+/// {
+///     println!("{:?}", part.header("Content-Range"));
+///     std::io::copy(&mut part, &mut std::io::stdout()).unwrap();
+/// }
+/// ```
+pub struct MultipartReader
+{
+	response: Response,
+	/// the CRLF, `--` and boundary that precede every part
+	delimiter: Vec<u8>,
+	buf: Vec<u8>,
+	pos: usize,
+	eof: bool,
+	done: bool,
+}
+
+/// One part of a [`MultipartReader`]
+///
+/// Reading it gives the part's content. The part doesn't need
+/// to be read entirely before the next one is requested.
+pub struct BodyPart<'a>
+{
+	headers: HeaderMap,
+	reader: &'a mut MultipartReader,
+}
+
+impl MultipartReader
+{
+	pub(crate) fn new(response: Response) -> Result<MultipartReader>
+	{
+		let boundary = response.header(CONTENT_TYPE)
+			.and_then(|t| t.to_str().ok())
+			.and_then(boundary);
+		let boundary =
+			match boundary
+			{
+				Some(b) => b,
+				None => return Err(Error::new(Kind::NotMultipart, Some(response.url().to_string()))),
+			};
+
+		let mut delimiter = b"\r\n--".to_vec();
+		delimiter.extend_from_slice(boundary.as_bytes());
+		Ok(MultipartReader
+		{
+			response,
+			delimiter,
+			// so that a delimiter at the very beginning is found too
+			buf: b"\r\n".to_vec(),
+			pos: 0,
+			eof: false,
+			done: false,
+		})
+	}
+
+	/// The response that this reads from
+	pub fn response(&self) -> &Response
+	{
+		&self.response
+	}
+
+	/// Returns the next part, or `None` after the last one
+	///
+	/// Whatever remains of the previous part is skipped. If the body
+	/// is malformed, the error contains an [`Error`] of kind
+	/// [`Kind::InvalidMultipart`](enum.Kind.html#variant.InvalidMultipart).
+	pub fn next_part(&mut self) -> std::io::Result<Option<BodyPart<'_>>>
+	{
+		if self.done
+			{ return Ok(None); }
+
+		// skip the preamble or the rest of the last part
+		let mut scratch = [0u8; 4096];
+		while self.read_content(&mut scratch)? != 0 {}
+		self.pos += self.delimiter.len();
+
+		self.fill(2)?;
+		if self.available().starts_with(b"--")
+		{
+			// the epilogue that follows is meant to be ignored
+			self.done = true;
+			return Ok(None);
+		}
+		let padding = self.read_line()?;
+		if !padding.iter().all(|&c| c == b' ' || c == b'\t')
+			{ return Err(self.invalid()); }
+
+		let mut headers = HeaderMap::new();
+		let mut size = 0;
+		loop
+		{
+			let line = self.read_line()?;
+			if line.is_empty()
+				{ break; }
+			size += line.len();
+			if size > HEADER_LIMIT
+				{ return Err(self.invalid()); }
+
+			let colon = line.iter().position(|&c| c == b':');
+			let header = colon.and_then(
+				|colon|
+					HeaderName::from_bytes(line[.. colon].trim_ascii()).ok()
+						.zip(HeaderValue::from_bytes(line[colon+1 ..].trim_ascii()).ok())
+			);
+			match header
+			{
+				Some((k,v)) => { headers.append(k, v); },
+				None => return Err(self.invalid()),
+			}
+		}
+
+		Ok(Some(BodyPart
+		{
+			headers,
+			reader: self,
+		}))
+	}
+
+	fn invalid(&self) -> std::io::Error
+	{
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidData,
+			Error::new(Kind::InvalidMultipart, Some(self.response.url().to_string())),
+		)
+	}
+
+	fn available(&self) -> &[u8]
+	{
+		&self.buf[self.pos ..]
+	}
+
+	/// Read until at least `n` bytes are available or the body ends
+	fn fill(&mut self, n: usize) -> std::io::Result<()>
+	{
+		while self.available().len() < n && !self.eof
+		{
+			if self.pos > 0
+			{
+				self.buf.drain(.. self.pos);
+				self.pos = 0;
+			}
+			let len = self.buf.len();
+			self.buf.resize(len + 8192, 0);
+			let n =
+				match self.response.read(&mut self.buf[len ..])
+				{
+					Ok(n) => n,
+					Err(e) =>
+					{
+						self.buf.truncate(len);
+						return Err(e);
+					},
+				};
+			self.buf.truncate(len + n);
+			if n == 0
+				{ self.eof = true; }
+		}
+		Ok(())
+	}
+
+	/// Read the line at the current position, without its CRLF
+	fn read_line(&mut self) -> std::io::Result<Vec<u8>>
+	{
+		loop
+		{
+			if let Some(end) = self.available().windows(2).position(|w| w == b"\r\n")
+			{
+				let line = self.available()[.. end].to_vec();
+				self.pos += end + 2;
+				return Ok(line);
+			}
+			if self.eof || self.available().len() > HEADER_LIMIT
+				{ return Err(self.invalid()); }
+			self.fill(self.available().len() + 1)?;
+		}
+	}
+
+	/// Read the content of the current part, stopping at the delimiter
+	fn read_content(&mut self, out: &mut [u8]) -> std::io::Result<usize>
+	{
+		if self.done || out.is_empty()
+			{ return Ok(0); }
+
+		let d = self.delimiter.len();
+		self.fill(d)?;
+		let available = self.available();
+
+		// a delimiter that starts within the first `out.len()` bytes
+		// must be entirely in this window
+		let window = &available[.. available.len().min(out.len() + d - 1)];
+		let n =
+			match window.windows(d).position(|w| w == self.delimiter)
+			{
+				Some(p) => p,
+				None if self.eof && window.len() == available.len() =>
+					return Err(self.invalid()),
+				None => out.len().min(available.len() + 1 - d),
+			};
+		out[.. n].copy_from_slice(&available[.. n]);
+		self.pos += n;
+		Ok(n)
+	}
+}
+
+impl std::fmt::Debug for MultipartReader
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.write_fmt(format_args!("MultipartReader {{code={}}}", self.response.status()))
+	}
+}
+
+/// The boundary parameter of a `multipart/*` content type
+fn boundary(content_type: &str) -> Option<String>
+{
+	let mime = content_type.split(';').next()?.trim();
+	if !mime.get(.. 10)?.eq_ignore_ascii_case("multipart/")
+		{ return None; }
+	content_type_param(content_type, "boundary")
+		.filter(|b| !b.is_empty())
+		.map(|b| b.to_string())
+}
+
+impl BodyPart<'_>
+{
+	/// Gets a specific header of this part by name
+	pub fn header<K: AsHeaderName>(&self, k: K) -> Option<&HeaderValue>
+	{
+		self.headers.get(k)
+	}
+
+	/// Gets a multimap of all the headers of this part
+	pub fn headers(&self) -> &HeaderMap
+	{
+		&self.headers
+	}
+}
+
+impl Read for BodyPart<'_>
+{
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>
+	{
+		self.reader.read_content(buf)
+	}
+}
+
+impl std::fmt::Debug for BodyPart<'_>
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.debug_struct("BodyPart")
+			.field("headers", &self.headers)
+			.finish()
+	}
+}
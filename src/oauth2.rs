@@ -0,0 +1,258 @@
+use std::sync::Arc;
+#[cfg(feature = "oauth2")]
+use std::sync::Mutex;
+#[cfg(feature = "oauth2")]
+use std::time::{Duration,Instant};
+
+use crate::*;
+
+/// Supplies the access tokens that a [`Client`] sends
+/// as `Authorization: Bearer`
+///
+/// See [`Client::token_provider`]. If the server answers
+/// 401 Unauthorized, the token is invalidated and the
+/// request is sent once more with a new one.
+pub trait TokenProvider: Send + Sync
+{
+	/// The token to send, which should be cached for as long as it's valid
+	fn token(&self) -> Result<String>;
+
+	/// The server rejected `token`, so the next call
+	/// to [`token`](#tymethod.token) should get a new one
+	fn invalidate(&self, token: &str);
+}
+
+impl<T: TokenProvider + ?Sized> TokenProvider for Arc<T>
+{
+	fn token(&self) -> Result<String>
+	{
+		(**self).token()
+	}
+
+	fn invalidate(&self, token: &str)
+	{
+		(**self).invalidate(token)
+	}
+}
+
+impl std::fmt::Debug for dyn TokenProvider
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.write_str("TokenProvider")
+	}
+}
+
+/// How long before it expires that a token is replaced
+#[cfg(feature = "oauth2")]
+const EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Gets tokens with the OAuth 2.0 client credentials grant
+///
+/// The client authenticates to the token endpoint with HTTP Basic.
+/// A token is reused until shortly before its `expires_in`.
+///
+/// ```no_run
+/// let tokens = idcurl::ClientCredentials::new(
+///     "https://auth.example.com/oauth2/token",
+///     "my-client",
+///     "my-secret",
+/// ).scope("read");
+/// let client = idcurl::Client::new()
+///     .token_provider(tokens);
+/// let response = client.get("https://api.example.com/things")
+///     .send()
+///     .unwrap();
+/// ```
+#[cfg(feature = "oauth2")]
+pub struct ClientCredentials
+{
+	token_url: String,
+	client_id: String,
+	client_secret: String,
+	scopes: Vec<String>,
+	client: Client,
+	cached: Mutex<Option<CachedToken>>,
+}
+
+#[cfg(feature = "oauth2")]
+struct CachedToken
+{
+	token: String,
+	expires: Option<Instant>,
+}
+
+#[cfg(feature = "oauth2")]
+impl ClientCredentials
+{
+	/// Get tokens from the endpoint at `token_url`
+	pub fn new(
+		token_url: impl Into<String>,
+		client_id: impl Into<String>,
+		client_secret: impl Into<String>,
+	) -> ClientCredentials
+	{
+		ClientCredentials
+		{
+			token_url: token_url.into(),
+			client_id: client_id.into(),
+			client_secret: client_secret.into(),
+			scopes: vec!(),
+			client: Client::new(),
+			cached: Mutex::new(None),
+		}
+	}
+
+	/// Ask for this scope, which may be called more than once
+	pub fn scope(mut self, scope: impl Into<String>) -> Self
+	{
+		self.scopes.push(scope.into());
+		self
+	}
+
+	/// Make the token requests with this client, for
+	/// example to set how TLS is verified
+	///
+	/// Its own token provider isn't used.
+	pub fn client(mut self, client: Client) -> Self
+	{
+		self.client = client;
+		self
+	}
+
+	fn fetch(&self) -> Result<CachedToken>
+	{
+		let mut fields = vec!(("grant_type", "client_credentials".to_string()));
+		if !self.scopes.is_empty()
+			{ fields.push(("scope", self.scopes.join(" "))); }
+
+		// RFC 6749 section 2.3.1 has them form-urlencoded first
+		let mut request = self.client.post(&self.token_url)
+			.auth(Auth::Basic
+			{
+				username: crate::url::escape(&self.client_id),
+				password: crate::url::escape(&self.client_secret),
+			})
+			.header(header::ACCEPT, "application/json")
+			.form(fields)
+			.error_for_status(true);
+		request.token_provider = None;
+		let mut response = request.send()?;
+
+		let body: TokenResponse = response.json()?;
+		let expires = body.expires_in
+			.map(|s| Instant::now() + Duration::from_secs(s).saturating_sub(EXPIRY_MARGIN));
+		Ok(CachedToken { token: body.access_token, expires })
+	}
+}
+
+/// A successful response from the token endpoint
+#[cfg(feature = "oauth2")]
+struct TokenResponse
+{
+	access_token: String,
+	expires_in: Option<u64>,
+}
+
+#[cfg(feature = "oauth2")]
+impl<'de> serde::Deserialize<'de> for TokenResponse
+{
+	fn deserialize<D>(d: D) -> std::result::Result<Self, D::Error>
+		where D: serde::Deserializer<'de>
+	{
+		struct Visitor;
+
+		impl<'de> serde::de::Visitor<'de> for Visitor
+		{
+			type Value = TokenResponse;
+
+			fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+			{
+				f.write_str("an OAuth 2.0 token response")
+			}
+
+			fn visit_map<A>(self, mut map: A) -> std::result::Result<TokenResponse, A::Error>
+				where A: serde::de::MapAccess<'de>
+			{
+				let mut access_token = None;
+				let mut expires_in = None;
+				while let Some(key) = map.next_key::<String>()?
+				{
+					match key.as_str()
+					{
+						"access_token" => access_token = Some(map.next_value()?),
+						// a number, but some servers send a string
+						"expires_in" =>
+							expires_in =
+								match map.next_value()?
+								{
+									serde_json::Value::Number(n) => n.as_u64(),
+									serde_json::Value::String(s) => s.parse().ok(),
+									_ => None,
+								},
+						_ => { map.next_value::<serde::de::IgnoredAny>()?; },
+					}
+				}
+				let access_token = access_token
+					.ok_or_else(|| serde::de::Error::missing_field("access_token"))?;
+				Ok(TokenResponse { access_token, expires_in })
+			}
+		}
+
+		d.deserialize_map(Visitor)
+	}
+}
+
+#[cfg(feature = "oauth2")]
+impl TokenProvider for ClientCredentials
+{
+	fn token(&self) -> Result<String>
+	{
+		// holding the lock means that only one token is requested at a time
+		let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+		let valid = cached.as_ref()
+			.is_some_and(|c| c.expires.is_none_or(|e| Instant::now() < e));
+		if !valid
+			{ *cached = Some(self.fetch()?); }
+		Ok(cached.as_ref().unwrap().token.clone())
+	}
+
+	fn invalidate(&self, token: &str)
+	{
+		let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+		if cached.as_ref().is_some_and(|c| c.token == token)
+			{ *cached = None; }
+	}
+}
+
+#[cfg(feature = "oauth2")]
+impl Drop for ClientCredentials
+{
+	fn drop(&mut self)
+	{
+		crate::auth::zeroize(unsafe { self.client_secret.as_mut_vec() });
+	}
+}
+
+#[cfg(feature = "oauth2")]
+impl Drop for CachedToken
+{
+	fn drop(&mut self)
+	{
+		crate::auth::zeroize(unsafe { self.token.as_mut_vec() });
+	}
+}
+
+#[cfg(feature = "oauth2")]
+impl std::fmt::Debug for ClientCredentials
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.debug_struct("ClientCredentials")
+			.field("token_url", &self.token_url)
+			.field("client_id", &self.client_id)
+			.field("client_secret", &"<redacted>")
+			.field("scopes", &self.scopes)
+			.finish()
+	}
+}
@@ -0,0 +1,430 @@
+use libc::c_ulong;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use crate::*;
+use crate::client::cr;
+use crate::header::*;
+use crate::request::CurlList;
+
+type SelectorFn = Arc<dyn Fn(&Url) -> Option<Proxy> + Send + Sync>;
+
+/// The function given to [`Client::proxy_selector`]
+#[derive(Clone)]
+pub(crate) struct ProxySelector(pub(crate) SelectorFn);
+
+impl std::fmt::Debug for ProxySelector
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.write_str("ProxySelector")
+	}
+}
+
+/// Instead of connecting directly to the host, connect via this proxy
+///
+/// A request that doesn't set one uses [`Proxy::FromEnvironment`].
+#[derive(Debug,Clone)]
+#[non_exhaustive]
+pub enum Proxy
+{
+	/// Connect to the SOCKS proxy at this address (a url)
+	/// Examples: `http://hostname`, `https://hostname`, `socks5://hostname`.
+	///
+	/// See [curl documentation](https://curl.se/libcurl/c/CURLOPT_PROXY.html)
+	Host(String),
+	/// Instead of making a TCP connection, connect to the Unix domain
+	/// socket with the given path.
+	//
+	// See [curl documentation](https://curl.se/libcurl/c/CURLOPT_UNIX_SOCKET_PATH.html)
+	UnixSocket(String),
+	/// Connect to the Linux abstract Unix domain socket with this
+	/// name, which doesn't include the leading nul byte
+	//
+	// See [curl documentation](https://curl.se/libcurl/c/CURLOPT_ABSTRACT_UNIX_SOCKET.html)
+	AbstractUnixSocket(String),
+	/// Connect to a proxy that needs more than its address,
+	/// such as credentials
+	Server(Box<ProxyServer>),
+	/// Use the proxy named by the environment, as curl does
+	///
+	/// For a URL with the scheme `https`, this is `https_proxy` or
+	/// `HTTPS_PROXY`, and likewise for other schemes, except that
+	/// only the lowercase `http_proxy` is used, because CGI
+	/// programs get a `HTTP_PROXY` from a client's `Proxy` header.
+	/// Otherwise it's `all_proxy` or `ALL_PROXY`. Hosts that match
+	/// `no_proxy` or `NO_PROXY` are connected to directly.
+	FromEnvironment,
+	/// Connect directly, even if the environment names a proxy
+	None,
+}
+
+impl From<ProxyServer> for Proxy
+{
+	fn from(server: ProxyServer) -> Proxy
+	{
+		Proxy::Server(Box::new(server))
+	}
+}
+
+impl From<ProxyServer> for Option<Proxy>
+{
+	fn from(server: ProxyServer) -> Option<Proxy>
+	{
+		Some(Proxy::Server(Box::new(server)))
+	}
+}
+
+/// A proxy with its credentials, headers and TLS settings
+///
+/// ```no_run
+/// let proxy = idcurl::ProxyServer::new("https://proxy.example.com:3128")
+///     .auth(idcurl::Auth::Basic
+///     {
+///         username: "user".to_string(),
+///         password: "secret".to_string(),
+///     })
+///     .header("X-Team", "billing")
+///     .tls(idcurl::TlsConfig::new().ca_file("/etc/ssl/proxy-ca.pem"));
+/// let response = idcurl::Request::get("http://example.com/".to_string())
+///     .proxy(proxy)
+///     .send()
+///     .unwrap();
+/// ```
+#[derive(Debug,Clone)]
+pub struct ProxyServer
+{
+	url: String,
+	auth: Option<Auth>,
+	headers: HeaderMap,
+	invalid_header: Option<String>,
+	tls: Option<TlsConfig>,
+	tunnel: bool,
+}
+
+impl ProxyServer
+{
+	/// The proxy at this URL, such as `http://hostname:3128`,
+	/// `https://hostname` or `socks5h://hostname`
+	pub fn new(url: impl Into<String>) -> ProxyServer
+	{
+		ProxyServer
+		{
+			url: url.into(),
+			auth: None,
+			headers: HeaderMap::new(),
+			invalid_header: None,
+			tls: None,
+			tunnel: false,
+		}
+	}
+
+	/// Authenticate to the proxy with these credentials
+	///
+	/// A proxy can use [`Auth::Basic`], [`Auth::Digest`],
+	/// [`Auth::Negotiate`] or [`Auth::Any`]. With any other,
+	/// requests fail.
+	pub fn auth(mut self, auth: Auth) -> Self
+	{
+		self.auth = Some(auth);
+		self
+	}
+
+	/// Send a header to the proxy but not to the server
+	///
+	/// If the name or value is not valid, requests fail with
+	/// [`Kind::InvalidHeader`](enum.Kind.html#variant.InvalidHeader).
+	pub fn header<K,V>(mut self, k: K, v: V) -> Self
+		where K: AsRef<[u8]>, V: AsRef<[u8]>
+	{
+		let (k,v) = (k.as_ref(), v.as_ref());
+		match HeaderName::from_bytes(k).ok().zip(HeaderValue::from_bytes(v).ok())
+		{
+			Some((k,v)) => { self.headers.append(k, v); },
+			None =>
+			{
+				self.invalid_header
+					.get_or_insert_with(|| String::from_utf8_lossy(k).into_owned());
+			},
+		}
+		self
+	}
+
+	/// Sets how the TLS connection to an `https://` proxy
+	/// is made and verified
+	///
+	/// This is separate from the TLS settings for the server.
+	/// [`TlsConfig::certificate_info`] has no effect here.
+	pub fn tls(mut self, tls: TlsConfig) -> Self
+	{
+		self.tls = Some(tls);
+		self
+	}
+
+	/// Ask an HTTP proxy for a tunnel with `CONNECT`,
+	/// even for `http://` URLs
+	///
+	/// Requests for `https://` URLs always use a tunnel.
+	pub fn tunnel(mut self, yes: bool) -> Self
+	{
+		self.tunnel = yes;
+		self
+	}
+}
+
+impl Proxy
+{
+	/// Whether `other` connects to the same proxy
+	pub(crate) fn same_as(&self, other: &Proxy) -> bool
+	{
+		match (self, other)
+		{
+			(Proxy::Host(a), Proxy::Host(b)) => a == b,
+			(Proxy::UnixSocket(a), Proxy::UnixSocket(b)) => a == b,
+			(Proxy::AbstractUnixSocket(a), Proxy::AbstractUnixSocket(b)) => a == b,
+			(Proxy::Server(a), Proxy::Server(b)) => a.url == b.url,
+			(Proxy::FromEnvironment, Proxy::FromEnvironment) => true,
+			(Proxy::None, Proxy::None) => true,
+			_ => false,
+		}
+	}
+
+	/// The socket in `unix_sockets` for the host of `url`, else the
+	/// proxy that `selector` picks for it, or else `proxy`
+	pub(crate) fn select(
+		url: &Url,
+		unix_sockets: &[(String, Proxy)],
+		selector: Option<&ProxySelector>,
+		proxy: Option<&Proxy>,
+		no_proxy: &NoProxy,
+	) -> Proxy
+	{
+		let socket = url.host().and_then(
+			|host|
+				unix_sockets.iter()
+					.find(|(h, _)| h.eq_ignore_ascii_case(host))
+					.map(|(_, socket)| socket)
+		);
+		if let Some(socket) = socket
+			{ return socket.clone(); }
+		let selected = selector.and_then(|s| (s.0)(url));
+		selected.as_ref()
+			.or(proxy)
+			.unwrap_or(&Proxy::FromEnvironment)
+			.for_url(url, no_proxy)
+	}
+
+	/// The proxy that a request for `url` goes through, which is
+	/// [`Proxy::None`] to connect directly
+	pub(crate) fn for_url(&self, url: &Url, no_proxy: &NoProxy) -> Proxy
+	{
+		let bypass = |list: &NoProxy| url.host().is_some_and(|h| list.matches(h));
+		match self
+		{
+			Proxy::UnixSocket(_) | Proxy::AbstractUnixSocket(_) | Proxy::None => self.clone(),
+			Proxy::Host(_) | Proxy::Server(_) if bypass(no_proxy) => Proxy::None,
+			Proxy::Host(_) | Proxy::Server(_) => self.clone(),
+			Proxy::FromEnvironment =>
+			{
+				if bypass(no_proxy) || bypass(&NoProxy::from_environment())
+					{ return Proxy::None; }
+				environment_proxy(url.scheme())
+					.map_or(Proxy::None, Proxy::Host)
+			},
+		}
+	}
+
+	/// Make `easy` connect via this proxy, keeping the
+	/// headers for the proxy in `headers` for as long as curl needs them
+	pub(crate) unsafe fn apply(&self, easy: *mut sys::CURL, headers: &mut CurlList)
+		-> Result<()>
+	{
+		match self
+		{
+			Proxy::Host(host) =>
+			{
+				let proxy_host = std::ffi::CString::new(host.as_str()).expect("making string");
+				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXY, proxy_host.as_ptr()))?;
+				no_bypass(easy)?;
+			},
+			Proxy::UnixSocket(path) =>
+			{
+				let proxy_host = std::ffi::CString::new(path.as_str()).expect("making string");
+				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_UNIX_SOCKET_PATH, proxy_host.as_ptr()))?;
+			},
+			Proxy::AbstractUnixSocket(name) =>
+			{
+				let name = std::ffi::CString::new(name.as_str())
+					.map_err(|_| Error::new(Kind::Curl("the socket name contains a nul byte".to_string()), None))?;
+				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_ABSTRACT_UNIX_SOCKET, name.as_ptr()))?;
+			},
+			Proxy::Server(server) =>
+			{
+				if let Some(name) = &server.invalid_header
+					{ return Err(Error::new(Kind::InvalidHeader(name.clone()), None)); }
+
+				let url = std::ffi::CString::new(server.url.as_str())
+					.map_err(|_| Error::new(Kind::Curl("the proxy URL contains a nul byte".to_string()), None))?;
+				cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXY, url.as_ptr()))?;
+				no_bypass(easy)?;
+				if let Some(auth) = &server.auth
+					{ auth.apply_proxy(easy)?; }
+				if !server.headers.is_empty()
+				{
+					*headers = CurlList::new();
+					headers.append_headers(&server.headers);
+					cr(sys::curl_easy_setopt(easy, ffi::CURLOPT_HEADEROPT, ffi::CURLHEADER_SEPARATE))?;
+					cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXYHEADER, headers.headers))?;
+				}
+				if let Some(tls) = &server.tls
+					{ tls.apply(easy, &crate::tls::PROXY)?; }
+				if server.tunnel
+					{ cr(sys::curl_easy_setopt(easy, sys::CURLOPT_HTTPPROXYTUNNEL, 1 as c_ulong))?; }
+			},
+			// for_url resolves this before a proxy is applied
+			Proxy::FromEnvironment => unreachable!(),
+			// an empty proxy stops curl from reading the environment
+			Proxy::None => cr(sys::curl_easy_setopt(easy, sys::CURLOPT_PROXY, c"".as_ptr()))?,
+		}
+		Ok(())
+	}
+}
+
+/// The host of a `no_proxy` entry, without any `:port`
+///
+/// An IPv6 address has more than one colon, so it's only
+/// taken to have a port if it's in brackets.
+fn without_port(entry: &str) -> &str
+{
+	if let Some(rest) = entry.strip_prefix('[')
+		{ return rest.split_once(']').map_or(rest, |(host, _)| host); }
+	match entry.split_once(':')
+	{
+		Some((host, port)) if !port.contains(':') => host,
+		_ => entry,
+	}
+}
+
+/// The proxy for `scheme` that the environment names
+fn environment_proxy(scheme: &str) -> Option<String>
+{
+	let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+	let name = format!("{}_proxy", scheme);
+	var(&name)
+		.or_else(|| if scheme == "http" { None } else { var(&name.to_ascii_uppercase()) })
+		.or_else(|| var("all_proxy"))
+		.or_else(|| var("ALL_PROXY"))
+}
+
+/// The hosts that are connected to directly even when there is a proxy
+///
+/// This is parsed from a comma-separated list in the format of the
+/// `no_proxy` environment variable, in which
+///
+/// * `*` matches every host,
+/// * a name such as `example.com` or `.example.com` matches
+///   that domain and its subdomains,
+/// * an IP address matches itself and, with a prefix length
+///   such as `10.0.0.0/8`, its network.
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub(crate) struct NoProxy
+{
+	entries: Vec<NoProxyEntry>,
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+enum NoProxyEntry
+{
+	All,
+	Domain(String),
+	Network(IpAddr, u8),
+}
+
+impl NoProxy
+{
+	pub(crate) fn new(list: &str) -> NoProxy
+	{
+		let entries = list.split(',')
+			.map(|e| e.trim())
+			.filter(|e| !e.is_empty())
+			.map(
+				|e|
+				{
+					if e == "*"
+						{ return NoProxyEntry::All; }
+					let (address, prefix) =
+						match e.split_once('/')
+						{
+							Some((a, p)) => (a, p.parse().ok()),
+							None => (e, None),
+						};
+					let address = without_port(address);
+					match address.parse::<IpAddr>()
+					{
+						Ok(ip) =>
+						{
+							let bits = if ip.is_ipv4() { 32 } else { 128 };
+							NoProxyEntry::Network(ip, prefix.unwrap_or(bits).min(bits))
+						},
+						Err(_) => NoProxyEntry::Domain(address.trim_start_matches('.').to_ascii_lowercase()),
+					}
+				}
+			)
+			.collect();
+		NoProxy { entries }
+	}
+
+	fn from_environment() -> NoProxy
+	{
+		let list = std::env::var("no_proxy")
+			.or_else(|_| std::env::var("NO_PROXY"))
+			.unwrap_or_default();
+		NoProxy::new(&list)
+	}
+
+	pub(crate) fn matches(&self, host: &str) -> bool
+	{
+		let host = host.trim_start_matches('[').trim_end_matches(']').trim_end_matches('.');
+		let ip = host.parse::<IpAddr>().ok();
+		self.entries.iter().any(
+			|e|
+				match e
+				{
+					NoProxyEntry::All => true,
+					NoProxyEntry::Domain(d) =>
+						host.eq_ignore_ascii_case(d)
+							|| (host.len() > d.len()
+								&& host.as_bytes()[host.len()-d.len()-1] == b'.'
+								&& host.as_bytes()[host.len()-d.len() ..].eq_ignore_ascii_case(d.as_bytes())),
+					NoProxyEntry::Network(net, prefix) =>
+						ip.is_some_and(|ip| in_network(ip, *net, *prefix)),
+				}
+		)
+	}
+}
+
+/// Stop curl from applying `no_proxy` from the environment, as
+/// [`Proxy::for_url`] has already decided to use the proxy
+unsafe fn no_bypass(easy: *mut sys::CURL) -> Result<()>
+{
+	cr(sys::curl_easy_setopt(easy, sys::CURLOPT_NOPROXY, c"".as_ptr()))
+}
+
+fn in_network(ip: IpAddr, net: IpAddr, prefix: u8) -> bool
+{
+	match (ip, net)
+	{
+		(IpAddr::V4(ip), IpAddr::V4(net)) =>
+		{
+			let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+			u32::from(ip) & mask == u32::from(net) & mask
+		},
+		(IpAddr::V6(ip), IpAddr::V6(net)) =>
+		{
+			let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+			u128::from(ip) & mask == u128::from(net) & mask
+		},
+		_ => false,
+	}
+}
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use crate::*;
+use crate::header::*;
+
+type RedirectCallback = Arc<dyn Fn(&Redirect) -> bool + Send + Sync>;
+
+/// Decides which redirects are followed
+///
+/// The number of redirects followed is set separately, with
+/// [`Request::redirect_limit`](struct.Request.html#method.redirect_limit).
+///
+/// When a redirect is not followed, the redirect itself is returned
+/// as the [`Response`], so you can examine its status and `Location`.
+///
+/// ```no_run
+/// let policy = idcurl::RedirectPolicy::new()
+///     .same_origin(true)
+///     .on_redirect(|r| !r.to().path().starts_with("/login"));
+/// let response = idcurl::Request::get("https://example.com/".to_string())
+///     .redirect_policy(policy)
+///     .send()
+///     .unwrap();
+/// ```
+#[derive(Clone,Default)]
+pub struct RedirectPolicy
+{
+	pub(crate) same_origin: bool,
+	pub(crate) deny_downgrade: bool,
+	pub(crate) keep_post: bool,
+	pub(crate) forward_authorization: bool,
+	pub(crate) on_redirect: Option<RedirectCallback>,
+}
+
+impl RedirectPolicy
+{
+	/// Create a policy that follows any http or https redirect
+	pub fn new() -> RedirectPolicy
+	{
+		Self::default()
+	}
+
+	/// Only follow redirects to the same scheme, host and port
+	pub fn same_origin(mut self, yes: bool) -> Self
+	{
+		self.same_origin = yes;
+		self
+	}
+
+	/// Don't follow redirects from `https` to `http`
+	pub fn deny_downgrade(mut self, yes: bool) -> Self
+	{
+		self.deny_downgrade = yes;
+		self
+	}
+
+	/// Keep a POST request a POST on a 301 or 302
+	///
+	/// By default, as browsers do, the request is changed to a GET
+	/// and the body is not sent again. A 303 always becomes a GET.
+	///
+	/// This is what [`CURLOPT_POSTREDIR`](https://curl.se/libcurl/c/CURLOPT_POSTREDIR.html)
+	/// does in curl.
+	pub fn keep_post(mut self, yes: bool) -> Self
+	{
+		self.keep_post = yes;
+		self
+	}
+
+	/// Send the `Authorization` and `Cookie` headers and the [`Auth`]
+	/// credentials to other hosts
+	///
+	/// By default, they are only sent to the origin of the original request.
+	/// A `Proxy-Authorization` header is only sent to the original proxy.
+	/// This is what [`CURLOPT_UNRESTRICTED_AUTH`](https://curl.se/libcurl/c/CURLOPT_UNRESTRICTED_AUTH.html)
+	/// does in curl.
+	pub fn forward_authorization(mut self, yes: bool) -> Self
+	{
+		self.forward_authorization = yes;
+		self
+	}
+
+	/// Call a function for each redirect, which returns `false`
+	/// to stop following
+	///
+	/// The function is only called for redirects that the rest
+	/// of this policy allows.
+	pub fn on_redirect<F>(mut self, f: F) -> Self
+		where F: Fn(&Redirect) -> bool + Send + Sync + 'static
+	{
+		self.on_redirect = Some(Arc::new(f));
+		self
+	}
+
+	pub(crate) fn allows(&self, r: &Redirect) -> bool
+	{
+		if !matches!(r.to.scheme(), "http" | "https")
+			{ return false; }
+		if self.same_origin && !r.from.same_origin(r.to)
+			{ return false; }
+		if self.deny_downgrade && r.from.scheme() == "https" && r.to.scheme() == "http"
+			{ return false; }
+		self.on_redirect.as_ref()
+			.is_none_or(|f| f(r))
+	}
+}
+
+impl std::fmt::Debug for RedirectPolicy
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.debug_struct("RedirectPolicy")
+			.field("same_origin", &self.same_origin)
+			.field("deny_downgrade", &self.deny_downgrade)
+			.field("keep_post", &self.keep_post)
+			.field("forward_authorization", &self.forward_authorization)
+			.field("on_redirect", &self.on_redirect.is_some())
+			.finish()
+	}
+}
+
+/// A redirect that is about to be followed
+///
+/// This is given to the function in [`RedirectPolicy::on_redirect`].
+#[derive(Debug)]
+pub struct Redirect<'a>
+{
+	pub(crate) status: StatusCode,
+	pub(crate) headers: &'a HeaderMap,
+	pub(crate) from: &'a Url,
+	pub(crate) to: &'a Url,
+	pub(crate) method: Method,
+	pub(crate) hops: usize,
+}
+
+impl Redirect<'_>
+{
+	/// The 3xx status of the redirect
+	pub fn status(&self) -> StatusCode
+	{
+		self.status
+	}
+
+	/// The headers of the redirect response
+	pub fn headers(&self) -> &HeaderMap
+	{
+		self.headers
+	}
+
+	/// The URL that responded with this redirect
+	pub fn from(&self) -> &Url
+	{
+		self.from
+	}
+
+	/// The URL that will be requested next
+	pub fn to(&self) -> &Url
+	{
+		self.to
+	}
+
+	/// The method that will be used for the next request
+	pub fn method(&self) -> Method
+	{
+		self.method
+	}
+
+	/// The number of redirects already followed
+	pub fn hops(&self) -> usize
+	{
+		self.hops
+	}
+}
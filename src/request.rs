@@ -2,6 +2,7 @@ use crate::*;
 use crate::client::*;
 use crate::method::*;
 use crate::response::*;
+use crate::header::*;
 
 /// Represent an unsent query.
 ///
@@ -10,34 +11,84 @@ pub struct Request<'body>
 {
 	pub(crate) method: Method,
 	pub(crate) url: Option<String>,
-	pub(crate) headers: Option<CurlList>,
+	pub(crate) headers: HeaderMap,
+	pub(crate) suppressed_headers: Vec<HeaderName>,
+	pub(crate) error: Option<Error>,
+	pub(crate) user_agent: Option<String>,
+	pub(crate) error_for_status: bool,
+	pub(crate) accept_encoding: bool,
+	pub(crate) decode_content: bool,
+	#[cfg(any(feature = "gzip", feature = "zstd"))]
+	pub(crate) compress_body: Option<Compression>,
+	pub(crate) tls: TlsConfig,
+	pub(crate) auth: Option<Auth>,
+	pub(crate) netrc: Netrc,
+	pub(crate) netrc_file: Option<std::path::PathBuf>,
+	pub(crate) token_provider: Option<std::sync::Arc<dyn TokenProvider>>,
 	pub(crate) redirect_limit: Option<usize>,
-	pub(crate) request_body: Option<Box<dyn std::io::Read + 'body>>,
+	pub(crate) redirect_policy: RedirectPolicy,
+	pub(crate) request_body: Option<Body<'body>>,
+	pub(crate) multipart: Option<Multipart<'body>>,
+	pub(crate) body_read: bool,
 	pub(crate) proxy: Option<Proxy>,
+	pub(crate) no_proxy: crate::proxy::NoProxy,
+	pub(crate) proxy_selector: Option<crate::proxy::ProxySelector>,
+	pub(crate) unix_sockets: Vec<(String, Proxy)>,
+	pub(crate) on_informational: Option<InformationalCallback>,
+	pub(crate) trailers: Option<TrailersCallback>,
 }
 
-/// Instead of connecting directly to the host, connect via this proxy
-#[derive(Debug)]
-#[non_exhaustive]
-pub enum Proxy
-{
-	/// Connect to the SOCKS proxy at this address (a url)
-	/// Examples: `http://hostname`, `https://hostname`, `socks5://hostname`.
-	///
-	/// See [curl documentation](https://curl.se/libcurl/c/CURLOPT_PROXY.html)
-	Host(String),
-	/// Instead of making a TCP connection, connect to the Unix domain
-	/// socket with the given path.
-	//
-	// See [curl documentation](https://curl.se/libcurl/c/CURLOPT_UNIX_SOCKET_PATH.html)
-	UnixSocket(String),
-}
+pub(crate) type TrailersCallback = Box<dyn FnMut() -> HeaderMap>;
 
 pub(crate) struct CurlList
 {
 	pub(crate) headers: *mut sys::curl_slist,
 }
 
+impl CurlList
+{
+	pub(crate) fn new() -> CurlList
+	{
+		CurlList{ headers: std::ptr::null_mut() }
+	}
+
+	/// Append one line, which must not contain a nul byte
+	pub(crate) fn push(&mut self, line: &[u8])
+	{
+		let mut h: Vec<u8> = Vec::with_capacity(line.len() + 1);
+		h.extend_from_slice(line);
+		h.push(b'\0');
+
+		unsafe
+		{
+			let l = sys::curl_slist_append(self.headers, h.as_ptr() as *const _);
+			assert!(!l.is_null());
+			self.headers = l;
+		}
+	}
+
+	/// Append the headers in the form curl expects
+	pub(crate) fn append_headers(&mut self, headers: &HeaderMap)
+	{
+		for (name, value) in headers
+		{
+			let mut h = Vec::with_capacity(name.as_str().len() + 2 + value.len());
+			h.extend_from_slice(name.as_str().as_bytes());
+			if value.is_empty()
+			{
+				// "name:" would tell curl not to send it at all
+				h.push(b';');
+			}
+			else
+			{
+				h.extend_from_slice(b": ");
+				h.extend_from_slice(value.as_bytes());
+			}
+			self.push(&h);
+		}
+	}
+}
+
 impl Drop for CurlList
 {
 	fn drop(&mut self)
@@ -61,10 +112,31 @@ impl<'body> Request<'body>
 			method,
 			url: Some(url),
 			//request_body: vec![].into(),
-			headers: Some( CurlList{ headers: std::ptr::null_mut() } ),
+			headers: HeaderMap::new(),
+			suppressed_headers: vec!(),
+			error: None,
+			user_agent: None,
+			error_for_status: false,
+			accept_encoding: false,
+			decode_content: true,
+			#[cfg(any(feature = "gzip", feature = "zstd"))]
+			compress_body: None,
+			tls: TlsConfig::new(),
+			auth: None,
+			netrc: Netrc::Ignored,
+			netrc_file: None,
+			token_provider: None,
 			redirect_limit: Some(10),
+			redirect_policy: RedirectPolicy::new(),
 			request_body: None,
+			multipart: None,
+			body_read: false,
 			proxy: None,
+			no_proxy: Default::default(),
+			proxy_selector: None,
+			unix_sockets: vec!(),
+			on_informational: None,
+			trailers: None,
 		}
 	}
 
@@ -128,43 +200,120 @@ impl<'body> Request<'body>
 	}
 
 	/// Set the proxy, which may also be a unix domain socket
+	///
+	/// `None` means [`Proxy::FromEnvironment`]. This replaces
+	/// the client's [`proxy_selector`](struct.Client.html#method.proxy_selector)
+	/// and [`unix_socket`](struct.Client.html#method.unix_socket)s.
 	pub fn set_proxy(&mut self, proxy: impl Into<Option<Proxy>>)
 	{
 		self.proxy = proxy.into();
+		self.proxy_selector = None;
+		self.unix_sockets.clear();
 	}
 
 	/// Set the proxy, which may also be a unix domain socket
+	///
+	/// `None` means [`Proxy::FromEnvironment`]. This replaces
+	/// the client's [`proxy_selector`](struct.Client.html#method.proxy_selector)
+	/// and [`unix_socket`](struct.Client.html#method.unix_socket)s.
 	pub fn proxy(mut self, proxy: impl Into<Option<Proxy>>) -> Self
 	{
-		self.proxy = proxy.into();
+		self.set_proxy(proxy);
+		self
+	}
+
+	/// Connect directly to these hosts instead of via a proxy
+	///
+	/// The list is in the format of the `no_proxy` environment
+	/// variable, for example `localhost,.internal.example.com,10.0.0.0/8`.
+	/// See [`Client::no_proxy`].
+	pub fn set_no_proxy(&mut self, hosts: &str) -> &mut Self
+	{
+		self.no_proxy = crate::proxy::NoProxy::new(hosts);
+		self
+	}
+
+	/// Connect directly to these hosts instead of via a proxy
+	///
+	/// See [`set_no_proxy`](#method.set_no_proxy).
+	pub fn no_proxy(mut self, hosts: &str) -> Self
+	{
+		self.set_no_proxy(hosts);
 		self
 	}
 
-	/// Add one HTTP header to the request.
+	/// Call a function for each informational (1xx) response
 	///
-	/// Any values may be sent, even those that may be invalid
-	/// according to the HTTP specification. You should
-	/// prefer to use the [`Header` constants](https://docs.rs/http/0.1.17/http/header/index.html)
-	/// in the `idcurl::Header` module.
+	/// The function is called as soon as the informational response
+	/// has been received, which lets you act on a `103 Early Hints`
+	/// before the final response arrives. The informational responses
+	/// are also available later from
+	/// [`Response::informational()`](struct.Response.html#method.informational).
+	pub fn set_on_informational<F>(&mut self, f: F)
+		where F: FnMut(&Informational) + 'static
+	{
+		self.on_informational = Some(Box::new(f));
+	}
+
+	/// Call a function for each informational (1xx) response
+	///
+	/// The function is called as soon as the informational response
+	/// has been received, which lets you act on a `103 Early Hints`
+	/// before the final response arrives. The informational responses
+	/// are also available later from
+	/// [`Response::informational()`](struct.Response.html#method.informational).
+	///
+	/// ```no_run
+	/// let response = idcurl::Request::get("http://example.com".to_string())
+	///     .on_informational(
+	///         |info|
+	///             for link in info.headers().get_all(idcurl::header::LINK)
+	///                 { eprintln!("preload {:?}", link); }
+	///     )
+	///     .send()
+	///     .unwrap();
+	/// ```
+	pub fn on_informational<F>(mut self, f: F) -> Self
+		where F: FnMut(&Informational) + 'static
+	{
+		self.set_on_informational(f);
+		self
+	}
+
+	/// Set one HTTP header of the request.
+	///
+	/// Any headers of the same name are replaced, use
+	/// [`append_header`](#method.append_header) to send
+	/// more than one.
+	///
+	/// You should prefer to use the [`Header` constants](https://docs.rs/http/1/http/header/index.html)
+	/// in the `idcurl::header` module. If the name or value is not
+	/// valid, [`send()`](#method.send) fails with
+	/// [`Kind::InvalidHeader`](enum.Kind.html#variant.InvalidHeader).
+	///
+	/// An empty value sends the header with no value.
 	pub fn set_header<K,V>(&mut self, k: K, v: V)
 		where K: AsRef<[u8]>, V: AsRef<[u8]>
 	{
-		let k = k.as_ref();
-		let v = v.as_ref();
-		let mut h: Vec<u8> = Vec::with_capacity(k.len() + 2 + v.len() + 1);
-		h.extend_from_slice(k);
-		h.extend_from_slice(b": ");
-		h.extend_from_slice(v);
-		h.push(b'\0');
-
-		unsafe
+		if let Some((k,v)) = self.parse_header(k.as_ref(), v.as_ref())
 		{
-			let l = sys::curl_slist_append(self.headers.as_ref().unwrap().headers, h.as_ptr() as *const _);
-			assert!(!l.is_null());
-			self.headers.as_mut().unwrap().headers = l;
+			self.suppressed_headers.retain(|s| *s != k);
+			self.headers.insert(k, v);
 		}
 	}
 
+	/// Set one HTTP header of the request.
+	///
+	/// Any headers of the same name are replaced, use
+	/// [`append_header`](#method.append_header) to send
+	/// more than one.
+	///
+	/// You should prefer to use the [`Header` constants](https://docs.rs/http/1/http/header/index.html)
+	/// in the `idcurl::header` module. If the name or value is not
+	/// valid, [`send()`](#method.send) fails with
+	/// [`Kind::InvalidHeader`](enum.Kind.html#variant.InvalidHeader).
+	///
+	/// An empty value sends the header with no value.
 	pub fn header<K,V>(mut self, k: K, v: V)
 		-> Self
 		where K: AsRef<[u8]>, V: AsRef<[u8]>
@@ -173,6 +322,215 @@ impl<'body> Request<'body>
 		self
 	}
 
+	/// Add one HTTP header to the request, keeping those
+	/// already set with the same name
+	pub fn append_header<K,V>(&mut self, k: K, v: V)
+		where K: AsRef<[u8]>, V: AsRef<[u8]>
+	{
+		if let Some((k,v)) = self.parse_header(k.as_ref(), v.as_ref())
+		{
+			self.suppressed_headers.retain(|s| *s != k);
+			self.headers.append(k, v);
+		}
+	}
+
+	/// Remove all the HTTP headers with this name, returning
+	/// the first one
+	pub fn remove_header<K: AsHeaderName>(&mut self, k: K) -> Option<HeaderValue>
+	{
+		self.headers.remove(k)
+	}
+
+	/// Don't send a header that curl would otherwise add
+	///
+	/// For example, curl sends `Accept: */*` unless told not to.
+	/// Any headers of the same name that were set are removed.
+	pub fn suppress_header<K: AsRef<[u8]>>(&mut self, k: K)
+	{
+		if let Some((k,_)) = self.parse_header(k.as_ref(), b"")
+		{
+			self.headers.remove(&k);
+			if !self.suppressed_headers.contains(&k)
+				{ self.suppressed_headers.push(k); }
+		}
+	}
+
+	/// The HTTP headers that will be sent
+	///
+	/// This doesn't include those that curl adds itself,
+	/// like `Host` or `Accept`.
+	pub fn headers(&self) -> &HeaderMap
+	{
+		&self.headers
+	}
+
+	/// The HTTP headers that will be sent, for modification
+	pub fn headers_mut(&mut self) -> &mut HeaderMap
+	{
+		&mut self.headers
+	}
+
+	fn parse_header(&mut self, k: &[u8], v: &[u8])
+		-> Option<(HeaderName,HeaderValue)>
+	{
+		let h = HeaderName::from_bytes(k).ok()
+			.zip(HeaderValue::from_bytes(v).ok());
+		if h.is_none() && self.error.is_none()
+		{
+			self.error = Some(Error::new(
+				Kind::InvalidHeader(String::from_utf8_lossy(k).into_owned()),
+				self.url.clone(),
+			));
+		}
+		h
+	}
+
+	/// the headers as curl should send them
+	pub(crate) fn curl_headers(&self) -> CurlList
+	{
+		let mut list = CurlList::new();
+		list.append_headers(&self.headers);
+		if let Some(auth) = &self.auth
+		{
+			let sends_body = self.method.sends_body()
+				&& (self.request_body.is_some() || self.multipart.is_some());
+			auth.append_headers(&mut list, &self.headers, sends_body);
+		}
+		for name in &self.suppressed_headers
+		{
+			let mut h = name.as_str().as_bytes().to_vec();
+			h.push(b':');
+			list.push(&h);
+		}
+		list
+	}
+
+	/// Send trailers, headers that follow the body
+	///
+	/// The function is called after the entire body has been read,
+	/// so it may include something calculated from the body, like
+	/// a checksum. It is called again each time the body is sent
+	/// again, such as after a `307` redirect.
+	///
+	/// Trailers are only sent with `Transfer-Encoding: chunked`,
+	/// so the body is sent that way even if its length is known, and
+	/// the [`content_length`](#method.content_length) must not be set.
+	pub fn set_trailers<F>(&mut self, f: F)
+		where F: FnMut() -> HeaderMap + 'static
+	{
+		self.trailers = Some(Box::new(f));
+	}
+
+	/// Send trailers, headers that follow the body
+	///
+	/// The function is called after the entire body has been read,
+	/// so it may include something calculated from the body, like
+	/// a checksum. It is called again each time the body is sent
+	/// again, such as after a `307` redirect.
+	///
+	/// Trailers are only sent with `Transfer-Encoding: chunked`,
+	/// so the body is sent that way even if its length is known, and
+	/// the [`content_length`](#method.content_length) must not be set.
+	pub fn trailers<F>(mut self, f: F) -> Self
+		where F: FnMut() -> HeaderMap + 'static
+	{
+		self.set_trailers(f);
+		self
+	}
+
+	/// Sends `value` as JSON
+	///
+	/// The body is replaced with the serialized `value` and
+	/// the `Content-Type` is set to `application/json`.
+	/// If `value` can't be serialized, [`send()`](#method.send) fails with
+	/// [`Kind::JsonEncode`](enum.Kind.html#variant.JsonEncode).
+	#[cfg(feature = "json")]
+	pub fn set_json<T>(&mut self, value: &T)
+		where T: serde::Serialize + ?Sized
+	{
+		match serde_json::to_vec(value)
+		{
+			Ok(v) =>
+			{
+				self.set_header(crate::header::CONTENT_TYPE, "application/json");
+				self.request_body = Some(Body::from(v));
+				self.multipart = None;
+			},
+			Err(e) =>
+			{
+				self.error.get_or_insert_with(
+					|| Error::new(Kind::JsonEncode(e), self.url.clone())
+				);
+			},
+		}
+	}
+
+	/// Sends `value` as JSON
+	///
+	/// The body is replaced with the serialized `value` and
+	/// the `Content-Type` is set to `application/json`.
+	/// If `value` can't be serialized, [`send()`](#method.send) fails with
+	/// [`Kind::JsonEncode`](enum.Kind.html#variant.JsonEncode).
+	///
+	/// ```no_run
+	/// # #[cfg(feature = "json")] {
+	/// let reply: std::collections::HashMap<String,String>
+	///     = idcurl::Request::post("http://example.com".to_string())
+	///         .json(&["hello", "world"])
+	///         .send()
+	///         .unwrap()
+	///         .json()
+	///         .unwrap();
+	/// # }
+	/// ```
+	#[cfg(feature = "json")]
+	pub fn json<T>(mut self, value: &T) -> Self
+		where T: serde::Serialize + ?Sized
+	{
+		self.set_json(value);
+		self
+	}
+
+	/// Sends the fields as an HTML form
+	///
+	/// The body is replaced with the fields, encoded as
+	/// `application/x-www-form-urlencoded`, which is also
+	/// set as the `Content-Type`.
+	pub fn set_form<I,K,V>(&mut self, fields: I)
+		where I: IntoIterator<Item=(K,V)>, K: AsRef<str>, V: AsRef<str>
+	{
+		let mut body = String::new();
+		for (k,v) in fields
+		{
+			if !body.is_empty() { body.push('&'); }
+			body.push_str(&crate::url::escape(k.as_ref()));
+			body.push('=');
+			body.push_str(&crate::url::escape(v.as_ref()));
+		}
+		self.set_header(crate::header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+		self.request_body = Some(Body::from(body));
+		self.multipart = None;
+	}
+
+	/// Sends the fields as an HTML form
+	///
+	/// The body is replaced with the fields, encoded as
+	/// `application/x-www-form-urlencoded`, which is also
+	/// set as the `Content-Type`.
+	///
+	/// ```no_run
+	/// let response = idcurl::Request::post("https://example.com/token".to_string())
+	///     .form([("grant_type", "client_credentials"), ("scope", "read write")])
+	///     .send()
+	///     .unwrap();
+	/// ```
+	pub fn form<I,K,V>(mut self, fields: I) -> Self
+		where I: IntoIterator<Item=(K,V)>, K: AsRef<str>, V: AsRef<str>
+	{
+		self.set_form(fields);
+		self
+	}
+
 	/// Sets the reader which the payload to send is read from
 	///
 	/// The entire body is read before [`send()`](#method.send) completes.
@@ -180,10 +538,11 @@ impl<'body> Request<'body>
 	/// The body is not read for the GET and DELETE methods.
 	///
 	/// The specified body, if a reference, must outlive this `Request`.
-	pub fn set_body<R: 'body>(&mut self, r: R)
+	pub fn set_body<R>(&mut self, r: R)
 		where R: std::io::Read + 'body
 	{
-		self.request_body = Some(Box::new(r));
+		self.request_body = Some(Body::reader(r));
+		self.multipart = None;
 	}
 
 	/// Sets the reader which the payload to send is read from
@@ -197,33 +556,107 @@ impl<'body> Request<'body>
 	/// can either give a reference with a reader (example:
 	/// `Cursor::new(&my_vector_object)`) or you can give ownership
 	/// (`Cursor::new(owned_vector)`).
-	pub fn body<'b, R: 'b>(self, r: R)
+	pub fn body<'b, R>(self, r: R)
 		-> Request<'b>
 		where R: std::io::Read + 'b
 	{
-		let request_body = Some(Box::new(r) as Box<dyn std::io::Read>);
+		let request_body = Some(Body::reader(r));
 
 		let Request
 			{
 				method,
 				url,
 				headers,
+				suppressed_headers,
+				error,
+				user_agent,
+				error_for_status,
+				accept_encoding,
+				decode_content,
+				#[cfg(any(feature = "gzip", feature = "zstd"))]
+				compress_body,
+				tls,
+				auth,
+				netrc,
+				netrc_file,
+				token_provider,
 				redirect_limit,
+				redirect_policy,
 				proxy,
+				no_proxy,
+				proxy_selector,
+				unix_sockets,
+				on_informational,
+				trailers,
 				..
 			} = self;
 
 		Request
 		{
-			method: method,
-			url: url,
-			headers: headers,
-			redirect_limit: redirect_limit,
+			method,
+			url,
+			headers,
+			suppressed_headers,
+			error,
+			user_agent,
+			error_for_status,
+			accept_encoding,
+			decode_content,
+			#[cfg(any(feature = "gzip", feature = "zstd"))]
+			compress_body,
+			tls,
+			auth,
+			netrc,
+			netrc_file,
+			token_provider,
+			redirect_limit,
+			redirect_policy,
 			request_body,
+			multipart: None,
+			body_read: false,
 			proxy,
+			no_proxy,
+			proxy_selector,
+			unix_sockets,
+			on_informational,
+			trailers,
 		}
 	}
 
+	/// Sends `form` as a `multipart/form-data` body
+	///
+	/// This replaces any other body. The form is only sent with the
+	/// POST, PUT and PATCH methods. If a part has an invalid header,
+	/// [`send()`](#method.send) fails with
+	/// [`Kind::InvalidHeader`](enum.Kind.html#variant.InvalidHeader).
+	pub fn set_multipart(&mut self, form: Multipart<'body>)
+	{
+		if let Some(h) = form.invalid_header()
+		{
+			self.error.get_or_insert_with(
+				|| Error::new(Kind::InvalidHeader(h.to_string()), self.url.clone())
+			);
+		}
+		self.request_body = None;
+		self.multipart = Some(form);
+	}
+
+	/// Sends `form` as a `multipart/form-data` body
+	///
+	/// This replaces any other body. The form is only sent with the
+	/// POST, PUT and PATCH methods. If a part has an invalid header,
+	/// [`send()`](#method.send) fails with
+	/// [`Kind::InvalidHeader`](enum.Kind.html#variant.InvalidHeader).
+	///
+	/// Like [`body`](#method.body), the returned `Request` has
+	/// the lifetime of the form's parts.
+	pub fn multipart<'b>(self, form: Multipart<'b>) -> Request<'b>
+	{
+		let mut request = self.body(std::io::empty());
+		request.set_multipart(form);
+		request
+	}
+
 	/// Make the HTTP request.
 	///
 	/// The configured request is sent along with its headers,
@@ -235,11 +668,82 @@ impl<'body> Request<'body>
 	/// initiated.
 	///
 	/// You should then call [`Response::status().is_success()`](status.html#method.is_success)
-	/// to check for an HTTP status code in the 200 range.
-	pub fn send(self) -> Result<Response>
+	/// to check for an HTTP status code in the 200 range,
+	/// unless [`error_for_status`](#method.error_for_status) is set.
+	pub fn send(mut self) -> Result<Response>
+	{
+		if let Some(e) = self.error.take()
+			{ return Err(e); }
+
+		// the request's own credentials take precedence
+		if let Some(provider) = self.token_provider.take()
+		{
+			if self.auth.is_none() && !self.headers.contains_key(AUTHORIZATION)
+			{
+				self.set_bearer_token(&provider.token()?)?;
+				self.token_provider = Some(provider);
+			}
+		}
+
+		#[cfg(any(feature = "gzip", feature = "zstd"))]
+		if let Some(c) = self.compress_body.filter(|_| self.request_body.is_some())
+		{
+			let body = self.request_body.take().unwrap();
+			let body = c.compress(body)
+				.map_err(|_| Error::new(Kind::BodyStreamFailure, self.url.clone()))?;
+			self.request_body = Some(body);
+			self.headers.remove(CONTENT_LENGTH);
+			self.set_header(CONTENT_ENCODING, c.content_encoding());
+		}
+
+		let error_for_status = self.error_for_status;
+		let response = Handle::new()
+			.execute(self)?;
+		if error_for_status
+			{ response.error_for_status() }
+		else
+			{ Ok(response) }
+	}
+
+	/// The proxy this request uses for `url`
+	pub(crate) fn proxy_for(&self, url: &Url) -> Proxy
+	{
+		Proxy::select(
+			url,
+			&self.unix_sockets,
+			self.proxy_selector.as_ref(),
+			self.proxy.as_ref(),
+			&self.no_proxy,
+		)
+	}
+
+	pub(crate) fn set_bearer_token(&mut self, token: &str) -> Result<()>
 	{
-		Client::new()
-			.execute(self)
+		let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+			.map_err(|_| Error::new(Kind::InvalidHeader(AUTHORIZATION.to_string()), self.url.clone()))?;
+		value.set_sensitive(true);
+		self.headers.insert(AUTHORIZATION, value);
+		Ok(())
+	}
+
+	/// Fail with [`Kind::HttpStatus`](enum.Kind.html#variant.HttpStatus)
+	/// on a 4xx or 5xx status
+	///
+	/// See [`Response::error_for_status`].
+	pub fn set_error_for_status(&mut self, yes: bool) -> &mut Self
+	{
+		self.error_for_status = yes;
+		self
+	}
+
+	/// Fail with [`Kind::HttpStatus`](enum.Kind.html#variant.HttpStatus)
+	/// on a 4xx or 5xx status
+	///
+	/// See [`Response::error_for_status`].
+	pub fn error_for_status(mut self, yes: bool) -> Self
+	{
+		self.set_error_for_status(yes);
+		self
 	}
 
 	/// sets the number of redirects that will be followed
@@ -269,4 +773,281 @@ impl<'body> Request<'body>
 		self.set_redirect_limit(n);
 		self
 	}
+
+	/// sets which redirects will be followed
+	///
+	/// By default, all redirects to http and https URLs
+	/// are followed, up to the [`redirect_limit`](#method.redirect_limit).
+	///
+	/// A redirect that needs the body to be sent again
+	/// (a 307 or 308 for a POST, for example) is only followed
+	/// if the body hasn't been read yet, or is a [`Body`] in memory.
+	pub fn set_redirect_policy(&mut self, policy: RedirectPolicy) -> &mut Self
+	{
+		self.redirect_policy = policy;
+		self
+	}
+
+	/// sets which redirects will be followed
+	///
+	/// By default, all redirects to http and https URLs
+	/// are followed, up to the [`redirect_limit`](#method.redirect_limit).
+	///
+	/// A redirect that needs the body to be sent again
+	/// (a 307 or 308 for a POST, for example) is only followed
+	/// if the body hasn't been read yet, or is a [`Body`] in memory.
+	pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self
+	{
+		self.set_redirect_policy(policy);
+		self
+	}
+
+	/// Sets how TLS connections are made and verified
+	pub fn set_tls(&mut self, tls: TlsConfig) -> &mut Self
+	{
+		self.tls = tls;
+		self
+	}
+
+	/// Sets how TLS connections are made and verified
+	pub fn tls(mut self, tls: TlsConfig) -> Self
+	{
+		self.set_tls(tls);
+		self
+	}
+
+	/// Authenticate with these credentials
+	///
+	/// Like the `Authorization` header, they are only sent to the
+	/// origin of the original request when it is redirected, unless
+	/// the [`RedirectPolicy`] forwards authorization.
+	pub fn set_auth(&mut self, auth: impl Into<Option<Auth>>) -> &mut Self
+	{
+		self.auth = auth.into();
+		self
+	}
+
+	/// Authenticate with these credentials
+	///
+	/// Like the `Authorization` header, they are only sent to the
+	/// origin of the original request when it is redirected, unless
+	/// the [`RedirectPolicy`] forwards authorization.
+	pub fn auth(mut self, auth: impl Into<Option<Auth>>) -> Self
+	{
+		self.set_auth(auth);
+		self
+	}
+
+	/// Take the username and password from a `.netrc` file
+	///
+	/// This is [`Netrc::Ignored`] by default.
+	pub fn set_netrc(&mut self, netrc: Netrc) -> &mut Self
+	{
+		self.netrc = netrc;
+		self
+	}
+
+	/// Take the username and password from a `.netrc` file
+	///
+	/// This is [`Netrc::Ignored`] by default.
+	///
+	/// ```no_run
+	/// let response = idcurl::Request::get("https://api.example.com/".to_string())
+	///     .netrc(idcurl::Netrc::Optional)
+	///     .send()
+	///     .unwrap();
+	/// ```
+	pub fn netrc(mut self, netrc: Netrc) -> Self
+	{
+		self.set_netrc(netrc);
+		self
+	}
+
+	/// Read this file instead of `~/.netrc`
+	///
+	/// It is only read if [`netrc`](#method.netrc) isn't [`Netrc::Ignored`].
+	pub fn set_netrc_file(&mut self, path: impl AsRef<std::path::Path>) -> &mut Self
+	{
+		self.netrc_file = Some(path.as_ref().to_path_buf());
+		self
+	}
+
+	/// Read this file instead of `~/.netrc`
+	///
+	/// It is only read if [`netrc`](#method.netrc) isn't [`Netrc::Ignored`].
+	pub fn netrc_file(mut self, path: impl AsRef<std::path::Path>) -> Self
+	{
+		self.set_netrc_file(path);
+		self
+	}
+
+	/// Sign the request with [AWS Signature Version 4](https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html)
+	///
+	/// This works with AWS and with compatible services such as
+	/// MinIO. The `provider` is the two names that curl puts in the
+	/// algorithm and the headers, which is `"aws:amz"` for AWS.
+	///
+	/// The body can't be hashed without reading it, so a request
+	/// with a body is sent with `X-Amz-Content-Sha256: UNSIGNED-PAYLOAD`.
+	/// To sign the body too, set that header to the body's SHA-256
+	/// in lowercase hexadecimal.
+	///
+	/// This replaces any other [`Auth`].
+	///
+	/// ```no_run
+	/// let credentials = idcurl::AwsCredentials::new("AKIDEXAMPLE", "secret");
+	/// let response = idcurl::Request::put("http://localhost:9000/bucket/key".to_string())
+	///     .aws_sigv4("aws:amz", "us-east-1", "s3", credentials)
+	///     .body(std::fs::File::open("upload.bin").unwrap())
+	///     .send()
+	///     .unwrap();
+	/// ```
+	pub fn set_aws_sigv4(
+		&mut self,
+		provider: &str,
+		region: &str,
+		service: &str,
+		credentials: AwsCredentials,
+	) -> &mut Self
+	{
+		self.set_auth(Auth::AwsSigV4
+		{
+			provider: provider.to_string(),
+			region: region.to_string(),
+			service: service.to_string(),
+			credentials,
+		})
+	}
+
+	/// Sign the request with AWS Signature Version 4
+	///
+	/// See [`set_aws_sigv4`](#method.set_aws_sigv4).
+	pub fn aws_sigv4(
+		mut self,
+		provider: &str,
+		region: &str,
+		service: &str,
+		credentials: AwsCredentials,
+	) -> Self
+	{
+		self.set_aws_sigv4(provider, region, service, credentials);
+		self
+	}
+
+	/// Compress the body as it is sent
+	///
+	/// The `Content-Encoding` is set and, because the compressed length
+	/// isn't known in advance, the body is sent with chunked encoding
+	/// instead of a `Content-Length`. A compressed body is not sent again
+	/// on a redirect. A [`Multipart`] body is not compressed.
+	///
+	/// Only send a compressed body to a server that you know accepts it.
+	///
+	/// Requires the `gzip` or `zstd` feature.
+	#[cfg(any(feature = "gzip", feature = "zstd"))]
+	pub fn set_compress_body(&mut self, compression: Option<Compression>) -> &mut Self
+	{
+		self.compress_body = compression;
+		self
+	}
+
+	/// Compress the body as it is sent
+	///
+	/// The `Content-Encoding` is set and, because the compressed length
+	/// isn't known in advance, the body is sent with chunked encoding
+	/// instead of a `Content-Length`. A compressed body is not sent again
+	/// on a redirect. A [`Multipart`] body is not compressed.
+	///
+	/// Only send a compressed body to a server that you know accepts it.
+	///
+	/// ```no_run
+	/// # #[cfg(feature = "gzip")] {
+	/// let batch = std::fs::File::open("events.json").unwrap();
+	/// let response = idcurl::Request::post("https://example.com/events".to_string())
+	///     .header("Content-Type", "application/json")
+	///     .body(batch)
+	///     .compress_body(idcurl::Compression::Gzip)
+	///     .send()
+	///     .unwrap();
+	/// # }
+	/// ```
+	///
+	/// Requires the `gzip` or `zstd` feature.
+	#[cfg(any(feature = "gzip", feature = "zstd"))]
+	pub fn compress_body(mut self, compression: impl Into<Option<Compression>>) -> Self
+	{
+		self.set_compress_body(compression.into());
+		self
+	}
+
+	/// Ask for a compressed response, which is decompressed as it's read
+	///
+	/// This sends an `Accept-Encoding` with every encoding that
+	/// curl was built to support, for example `gzip` and `deflate`.
+	/// The response's `Content-Encoding` and `Content-Length` headers
+	/// still describe the body as it was sent.
+	pub fn set_accept_encoding(&mut self, yes: bool) -> &mut Self
+	{
+		self.accept_encoding = yes;
+		self
+	}
+
+	/// Ask for a compressed response, which is decompressed as it's read
+	///
+	/// This sends an `Accept-Encoding` with every encoding that
+	/// curl was built to support, for example `gzip` and `deflate`.
+	/// The response's `Content-Encoding` and `Content-Length` headers
+	/// still describe the body as it was sent.
+	pub fn accept_encoding(mut self, yes: bool) -> Self
+	{
+		self.set_accept_encoding(yes);
+		self
+	}
+
+	/// Sets whether a compressed response is decompressed
+	///
+	/// The default is `true`. With `false`, the body is read
+	/// exactly as the server sent it, even if
+	/// [`accept_encoding`](#method.accept_encoding) is set.
+	pub fn set_decode_content(&mut self, yes: bool) -> &mut Self
+	{
+		self.decode_content = yes;
+		self
+	}
+
+	/// Sets whether a compressed response is decompressed
+	///
+	/// The default is `true`. With `false`, the body is read
+	/// exactly as the server sent it, even if
+	/// [`accept_encoding`](#method.accept_encoding) is set.
+	pub fn decode_content(mut self, yes: bool) -> Self
+	{
+		self.set_decode_content(yes);
+		self
+	}
+}
+
+impl<'b, B> TryFrom<http::Request<B>> for Request<'b>
+	where B: Into<Body<'b>>
+{
+	type Error = Error;
+
+	/// Make a `Request` with the method, URI, headers and body
+	/// of an `http::Request`
+	///
+	/// The HTTP version and extensions are ignored. Fails with
+	/// [`Kind::UnsupportedMethod`](enum.Kind.html#variant.UnsupportedMethod)
+	/// for methods other than those in [`Method`].
+	fn try_from(r: http::Request<B>) -> Result<Request<'b>>
+	{
+		let (parts, body) = r.into_parts();
+		let url = parts.uri.to_string();
+		let method = Method::try_from(&parts.method)
+			.map_err(|e| Error::new(e.into_kind(), Some(url.clone())))?;
+
+		let mut request = Request::new(method, url);
+		request.headers = parts.headers;
+		request.request_body = Some(body.into());
+		Ok(request)
+	}
 }
@@ -5,6 +5,8 @@ use crate::client::*;
 use crate::header::*;
 use crate::*;
 
+pub(crate) type InformationalCallback = Box<dyn FnMut(&Informational)>;
+
 pub(crate) struct ResponseData
 {
 	pub(crate) read_queue: VecDeque<u8>,
@@ -12,6 +14,19 @@ pub(crate) struct ResponseData
 	pub(crate) completed: bool,
 	pub(crate) headers: HeaderMap,
 	pub(crate) status_code: StatusCode,
+	/// the status of the header block currently being received
+	pub(crate) header_status: Option<StatusCode>,
+	pub(crate) informational: Vec<Informational>,
+	pub(crate) on_informational: Option<InformationalCallback>,
+	/// the final response's headers are done, the rest are trailers
+	pub(crate) in_trailers: bool,
+	pub(crate) trailers: HeaderMap,
+	/// the handle to ask about TLS, null if the URL isn't https
+	pub(crate) tls_easy: *mut sys::CURL,
+	pub(crate) peer_certificates: Vec<PeerCertificate>,
+	/// the negotiated TLS version and cipher
+	#[cfg(feature = "tls-session")]
+	pub(crate) tls_session: Option<(String, String)>,
 }
 
 impl ResponseData
@@ -25,10 +40,84 @@ impl ResponseData
 			completed: false,
 			headers: HeaderMap::new(),
 			status_code: StatusCode::NOT_IMPLEMENTED,
+			header_status: None,
+			informational: vec!(),
+			on_informational: None,
+			in_trailers: false,
+			trailers: HeaderMap::new(),
+			tls_easy: std::ptr::null_mut(),
+			peer_certificates: vec!(),
+			#[cfg(feature = "tls-session")]
+			tls_session: None,
 		}
 	}
 }
 
+impl ResponseData
+{
+	/// forget the response to a request that is being redirected
+	pub(crate) fn restart(&mut self)
+	{
+		self.read_queue.clear();
+		self.headers_done = false;
+		self.completed = false;
+		self.headers.clear();
+		self.header_status = None;
+		self.in_trailers = false;
+		self.trailers.clear();
+		self.informational.clear();
+		self.peer_certificates.clear();
+		#[cfg(feature = "tls-session")]
+		{ self.tls_session = None; }
+	}
+}
+
+/// An informational (1xx) response that preceded the final one
+///
+/// Servers may send any number of these, for example
+/// `100 Continue` or `103 Early Hints`. Their headers are
+/// kept separate from the final [`Response`]'s headers.
+#[derive(Debug,Clone)]
+pub struct Informational
+{
+	pub(crate) status: StatusCode,
+	pub(crate) headers: HeaderMap,
+}
+
+impl Informational
+{
+	/// Returns the 1xx HTTP status code
+	pub fn status(&self) -> StatusCode
+	{
+		self.status
+	}
+
+	/// Gets a specific HTTP header by name
+	pub fn header<K: AsHeaderName>(&self, k: K) -> Option<&HeaderValue>
+	{
+		self.headers.get(k)
+	}
+
+	/// Gets a multimap of all HTTP headers in this response
+	pub fn headers(&self) -> &HeaderMap
+	{
+		&self.headers
+	}
+}
+
+/// The value of a parameter of a `Content-Type`, like `charset`
+pub(crate) fn content_type_param<'a>(content_type: &'a str, name: &str) -> Option<&'a str>
+{
+	content_type.split(';')
+		.skip(1)
+		.filter_map(|p| p.split_once('='))
+		.find(|(k, _)| k.trim().eq_ignore_ascii_case(name))
+		.map(|(_, v)| v.trim().trim_matches('"'))
+}
+
+/// The most of the body kept by [`Response::error_for_status`]
+pub const ERROR_BODY_LIMIT: u64 = 16*1024;
+
 /// Represents the result of an HTTP request
 ///
 /// This object implements `Read`, which means
@@ -36,8 +125,9 @@ impl ResponseData
 /// use the accessors to read it into memory.
 pub struct Response
 {
-	pub(crate) client: Client,
+	pub(crate) handle: Handle,
 	pub(crate) rd: Box<ResponseData>,
+	pub(crate) url: Url,
 //	pub(crate) h: RefCell<curl::multi::Easy2Handle<Tranceiver>>,
 //	pub(crate) tx: Rc<RefCell<TranceiverData>>,
 //	pub(crate) multi: curl::multi::Multi,
@@ -54,6 +144,87 @@ impl Response
 		self.rd.status_code
 	}
 
+	/// The URL that this is the response of, after following redirects
+	pub fn url(&self) -> &Url
+	{
+		&self.url
+	}
+
+	/// Returns the version of HTTP that was used
+	pub fn version(&self) -> http::Version
+	{
+		let mut v: libc::c_long = 0;
+		unsafe
+		{
+			sys::curl_easy_getinfo(
+				self.handle.easy,
+				crate::ffi::CURLINFO_HTTP_VERSION,
+				&mut v as *mut _
+			);
+		}
+		match v as libc::c_int
+		{
+			sys::CURL_HTTP_VERSION_1_0 => http::Version::HTTP_10,
+			sys::CURL_HTTP_VERSION_2_0 => http::Version::HTTP_2,
+			sys::CURL_HTTP_VERSION_3 => http::Version::HTTP_3,
+			_ => http::Version::HTTP_11,
+		}
+	}
+
+	/// Converts this into an `http::Response` whose body is
+	/// this `Response`, to be read as before
+	///
+	/// The status, version and headers are copied.
+	pub fn into_http(self) -> http::Response<Response>
+	{
+		let mut r = http::Response::new(());
+		*r.status_mut() = self.status();
+		*r.version_mut() = self.version();
+		*r.headers_mut() = self.headers().clone();
+		r.map(|_| self)
+	}
+
+	/// Splits this into the parts of an `http::Response` and
+	/// the body, which is this `Response`
+	pub fn into_parts(self) -> (http::response::Parts, Response)
+	{
+		self.into_http().into_parts()
+	}
+
+	/// Turns a 4xx or 5xx status into an error
+	///
+	/// The error is [`Kind::HttpStatus`](enum.Kind.html#variant.HttpStatus),
+	/// which includes the headers and the beginning of the body,
+	/// like curl's `--fail-with-body`. Other responses are returned
+	/// unchanged.
+	///
+	/// ```no_run
+	/// let data = idcurl::get("http://example.com")
+	///     .and_then(|r| r.error_for_status())
+	///     .expect("request failed")
+	///     .data()
+	///     .unwrap();
+	/// ```
+	pub fn error_for_status(mut self) -> Result<Response>
+	{
+		let status = self.status();
+		if !status.is_client_error() && !status.is_server_error()
+			{ return Ok(self); }
+
+		let mut body = vec!();
+		// the body is only for diagnostics, it doesn't matter if it's incomplete
+		let _ = (&mut self).take(ERROR_BODY_LIMIT).read_to_end(&mut body);
+		Err(Error::new(
+			Kind::HttpStatus
+			{
+				status,
+				headers: Box::new(std::mem::take(&mut self.rd.headers)),
+				body,
+			},
+			Some(self.url.to_string()),
+		))
+	}
+
 	/// Gets the Content-Length of the returned body.
 	///
 	/// If the server reported the length of the returned body,
@@ -70,7 +241,9 @@ impl Response
 
 	/// Read the entire document and interpret it as UTF-8.
 	///
-	/// Read the entire message body into memory.
+	/// Read the entire message body into memory. To honour the
+	/// `charset` of the response, use [`text()`](#method.text)
+	/// with the `charset` feature.
 	pub fn text_as_utf8(&mut self) -> std::io::Result<String>
 	{
 		String::from_utf8(self.data()?)
@@ -83,6 +256,101 @@ impl Response
 			)
 	}
 
+	/// Read the entire document and decode it as text
+	///
+	/// The encoding is taken from a byte order mark, or else from
+	/// the `charset` of the `Content-Type`, and is UTF-8 if
+	/// there is neither. If the charset isn't known or the text is
+	/// not valid in it, the error contains an [`Error`] of kind
+	/// [`Kind::UnknownCharset`](enum.Kind.html#variant.UnknownCharset) or
+	/// [`Kind::InvalidText`](enum.Kind.html#variant.InvalidText).
+	#[cfg(feature = "charset")]
+	pub fn text(&mut self) -> std::io::Result<String>
+	{
+		let data = self.data()?;
+		let invalid =
+			|kind|
+				std::io::Error::new(
+					std::io::ErrorKind::InvalidData,
+					Error::new(kind, Some(self.url.to_string())),
+				);
+		let (encoding, bom) =
+			match encoding_rs::Encoding::for_bom(&data)
+			{
+				Some(bom) => bom,
+				None =>
+				(
+					self.encoding().map_err(|c| invalid(Kind::UnknownCharset(c)))?,
+					0,
+				),
+			};
+		encoding.decode_without_bom_handling_and_without_replacement(&data[bom ..])
+			.map(|t| t.into_owned())
+			.ok_or_else(|| invalid(Kind::InvalidText(encoding.name().to_string())))
+	}
+
+	/// Read the entire document and decode it as text, replacing
+	/// what isn't valid
+	///
+	/// The encoding is chosen as with [`text()`](#method.text), but a charset
+	/// that isn't known is treated as UTF-8 and anything that
+	/// can't be decoded becomes `U+FFFD`.
+	#[cfg(feature = "charset")]
+	pub fn text_lossy(&mut self) -> std::io::Result<String>
+	{
+		let data = self.data()?;
+		let encoding = self.encoding().unwrap_or(encoding_rs::UTF_8);
+		// this also prefers a byte order mark
+		let (text, _, _) = encoding.decode(&data);
+		Ok(text.into_owned())
+	}
+
+	/// The encoding named by the `charset` of the `Content-Type`,
+	/// or the charset if it is not known
+	#[cfg(feature = "charset")]
+	fn encoding(&self) -> std::result::Result<&'static encoding_rs::Encoding, String>
+	{
+		let charset = self.header(CONTENT_TYPE)
+			.and_then(|t| t.to_str().ok())
+			.and_then(|t| content_type_param(t, "charset"));
+		match charset
+		{
+			None => Ok(encoding_rs::UTF_8),
+			Some(c) => encoding_rs::Encoding::for_label(c.as_bytes())
+				.ok_or_else(|| c.to_string()),
+		}
+	}
+
+	/// Read the entire document and deserialize it from JSON
+	///
+	/// The body is deserialized as it's read, without
+	/// being buffered in memory. If it isn't the expected JSON,
+	/// this fails with [`Kind::JsonDecode`](enum.Kind.html#variant.JsonDecode),
+	/// which includes the status, because error responses often
+	/// have a different body.
+	#[cfg(feature = "json")]
+	pub fn json<T>(&mut self) -> Result<T>
+		where T: serde::de::DeserializeOwned
+	{
+		serde_json::from_reader(std::io::BufReader::new(&mut *self))
+			.map_err(
+				|error|
+					Error::new(
+						Kind::JsonDecode{ status: self.status(), error },
+						Some(self.url.to_string()),
+					)
+			)
+	}
+
+	/// Read the body as a `multipart/*` response, one part at a time
+	///
+	/// Fails with [`Kind::NotMultipart`](enum.Kind.html#variant.NotMultipart)
+	/// if the `Content-Type` is not `multipart/*` with a boundary.
+	pub fn multipart(self) -> Result<MultipartReader>
+	{
+		MultipartReader::new(self)
+	}
+
 	/// Copies this Read object into another Write object
 	///
 	/// Returns the number of bytes read or an Error
@@ -109,11 +377,69 @@ impl Response
 	}
 
 	/// Gets a multimap of all HTTP headers received
+	///
+	/// Only the headers of the final response are included, those
+	/// of informational responses are in [`informational()`](#method.informational).
 	pub fn headers(&self) -> &HeaderMap
 	{
 		&self.rd.headers
 	}
 
+	/// Gets the trailers, the headers sent after the body
+	///
+	/// Trailers can only be sent with a chunked body, and
+	/// they are only known once the body has been entirely read,
+	/// until then this returns `None`.
+	pub fn trailers(&self) -> Option<&HeaderMap>
+	{
+		if self.rd.completed && self.rd.read_queue.is_empty()
+			{ Some(&self.rd.trailers) }
+		else
+			{ None }
+	}
+
+	/// The informational (1xx) responses received before this one
+	///
+	/// These are in the order the server sent them.
+	pub fn informational(&self) -> &[Informational]
+	{
+		&self.rd.informational
+	}
+
+	/// The certificate chain that the server presented, starting
+	/// with its own certificate
+	///
+	/// This is empty unless [`TlsConfig::certificate_info`] is
+	/// enabled and the connection used TLS.
+	pub fn peer_certificates(&self) -> &[PeerCertificate]
+	{
+		&self.rd.peer_certificates
+	}
+
+	/// The TLS version that was negotiated, such as `"TLSv1.3"`
+	///
+	/// This is `None` if the connection didn't use TLS.
+	///
+	/// This needs the `tls-session` feature, which builds curl
+	/// statically with the OpenSSL of `openssl-sys` instead of
+	/// using the system's libcurl.
+	#[cfg(feature = "tls-session")]
+	pub fn tls_version(&self) -> Option<&str>
+	{
+		self.rd.tls_session.as_ref().map(|(v, _)| v.as_str())
+	}
+
+	/// The name of the TLS cipher that was negotiated,
+	/// such as `"TLS_AES_256_GCM_SHA384"`
+	///
+	/// This is `None` if the connection didn't use TLS, and needs the
+	/// `tls-session` feature, as [`tls_version`](#method.tls_version) does.
+	#[cfg(feature = "tls-session")]
+	pub fn tls_cipher(&self) -> Option<&str>
+	{
+		self.rd.tls_session.as_ref().map(|(_, c)| c.as_str())
+	}
+
 	/// The remote ip address for this connection
 	pub fn remote_address(&self) -> Result<&str>
 	{
@@ -122,7 +448,7 @@ impl Response
 			let mut p: *const i8 = std::ptr::null_mut();
 
 			crate::client::cr(sys::curl_easy_getinfo(
-				self.client.easy,
+				self.handle.easy,
 				sys::CURLINFO_PRIMARY_IP,
 				&mut p
 			))?;
@@ -151,18 +477,19 @@ impl std::io::Read for Response
 		let mut pos = 0;
 		while pos != buf.len()
 		{
-			if self.rd.read_queue.len() == 0 && !self.rd.completed
+			if self.rd.read_queue.is_empty() && !self.rd.completed
 			{
-				let e = self.client.wait_and_process()
-					.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
-				if e.is_err()
+				match self.handle.wait_and_process()
 				{
-					self.rd.completed = true;
-					return Err(e.unwrap_err());
+					Ok(done) => self.rd.completed = done,
+					Err(e) =>
+					{
+						self.rd.completed = true;
+						return Err(std::io::Error::other(e));
+					},
 				}
-				self.rd.completed = e.unwrap();
 			}
-			if self.rd.read_queue.len() == 0 && self.rd.completed
+			if self.rd.read_queue.is_empty() && self.rd.completed
 			{
 				break;
 			}
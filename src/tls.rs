@@ -0,0 +1,623 @@
+use libc::{c_long,c_ulong};
+
+use std::ffi::CString;
+use std::path::{Path,PathBuf};
+
+use crate::*;
+use crate::client::cr;
+
+/// A version of TLS
+#[derive(Debug,Clone,Copy,PartialEq,Eq,PartialOrd,Ord)]
+pub enum TlsVersion
+{
+	Tls1_0,
+	Tls1_1,
+	Tls1_2,
+	Tls1_3,
+}
+
+impl TlsVersion
+{
+	fn curl(self) -> c_long
+	{
+		(match self
+		{
+			TlsVersion::Tls1_0 => sys::CURL_SSLVERSION_TLSv1_0,
+			TlsVersion::Tls1_1 => sys::CURL_SSLVERSION_TLSv1_1,
+			TlsVersion::Tls1_2 => sys::CURL_SSLVERSION_TLSv1_2,
+			TlsVersion::Tls1_3 => sys::CURL_SSLVERSION_TLSv1_3,
+		}) as c_long
+	}
+}
+
+/// The format of a client certificate or key
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum CertificateFormat
+{
+	Pem,
+	Der,
+	/// PKCS#12, which holds both the certificate and the key;
+	/// only for a certificate
+	P12,
+}
+
+impl CertificateFormat
+{
+	fn curl(self) -> &'static std::ffi::CStr
+	{
+		match self
+		{
+			CertificateFormat::Pem => c"PEM",
+			CertificateFormat::Der => c"DER",
+			CertificateFormat::P12 => c"P12",
+		}
+	}
+}
+
+#[derive(Clone)]
+enum Source
+{
+	File(PathBuf),
+	Blob(Vec<u8>),
+}
+
+#[derive(Clone,Debug)]
+enum Pin
+{
+	Hashes(Vec<String>),
+	File(PathBuf),
+}
+
+/// How TLS connections are made and verified
+///
+/// By default, curl's own CA bundle is used and the server's
+/// certificate and host name are always verified.
+///
+/// ```no_run
+/// let tls = idcurl::TlsConfig::new()
+///     .ca_file("/etc/ssl/internal-ca.pem")
+///     .client_cert_file("client.pem", idcurl::CertificateFormat::Pem)
+///     .client_key_file("client.key", idcurl::CertificateFormat::Pem)
+///     .key_password("secret")
+///     .min_version(idcurl::TlsVersion::Tls1_2);
+/// let response = idcurl::Request::get("https://internal.example.com/".to_string())
+///     .tls(tls)
+///     .send()
+///     .unwrap();
+/// ```
+#[derive(Clone,Default)]
+pub struct TlsConfig
+{
+	ca_file: Option<PathBuf>,
+	ca_dir: Option<PathBuf>,
+	ca_pem: Option<Vec<u8>>,
+	client_cert: Option<(Source, CertificateFormat)>,
+	client_key: Option<(Source, CertificateFormat)>,
+	key_password: Option<String>,
+	min_version: Option<TlsVersion>,
+	max_version: Option<TlsVersion>,
+	ciphers: Option<String>,
+	tls13_ciphers: Option<String>,
+	pinned_public_key: Option<Pin>,
+	accept_invalid_certs: bool,
+	accept_invalid_hostnames: bool,
+	certificate_info: bool,
+}
+
+impl TlsConfig
+{
+	/// The default configuration, which verifies everything
+	pub fn new() -> TlsConfig
+	{
+		Self::default()
+	}
+
+	/// Verify the server with the CA certificates in this PEM file
+	/// instead of curl's default bundle
+	pub fn ca_file(mut self, path: impl AsRef<Path>) -> Self
+	{
+		self.ca_file = Some(path.as_ref().to_path_buf());
+		self
+	}
+
+	/// Verify the server with the CA certificates in this directory,
+	/// which must have been prepared with `openssl rehash`
+	pub fn ca_dir(mut self, path: impl AsRef<Path>) -> Self
+	{
+		self.ca_dir = Some(path.as_ref().to_path_buf());
+		self
+	}
+
+	/// Verify the server with these PEM encoded CA certificates,
+	/// instead of those in [`ca_file`](#method.ca_file)
+	pub fn ca_pem(mut self, pem: impl Into<Vec<u8>>) -> Self
+	{
+		self.ca_pem = Some(pem.into());
+		self
+	}
+
+	/// Present the client certificate in this file
+	pub fn client_cert_file(mut self, path: impl AsRef<Path>, format: CertificateFormat) -> Self
+	{
+		self.client_cert = Some((Source::File(path.as_ref().to_path_buf()), format));
+		self
+	}
+
+	/// Present this client certificate
+	pub fn client_cert(mut self, cert: impl Into<Vec<u8>>, format: CertificateFormat) -> Self
+	{
+		self.client_cert = Some((Source::Blob(cert.into()), format));
+		self
+	}
+
+	/// The private key of the client certificate is in this file
+	pub fn client_key_file(mut self, path: impl AsRef<Path>, format: CertificateFormat) -> Self
+	{
+		self.client_key = Some((Source::File(path.as_ref().to_path_buf()), format));
+		self
+	}
+
+	/// This is the private key of the client certificate
+	pub fn client_key(mut self, key: impl Into<Vec<u8>>, format: CertificateFormat) -> Self
+	{
+		self.client_key = Some((Source::Blob(key.into()), format));
+		self
+	}
+
+	/// The password that the client key, or a PKCS#12
+	/// certificate, is encrypted with
+	pub fn key_password(mut self, password: impl Into<String>) -> Self
+	{
+		self.key_password = Some(password.into());
+		self
+	}
+
+	/// Don't connect with a version of TLS older than this
+	pub fn min_version(mut self, version: TlsVersion) -> Self
+	{
+		self.min_version = Some(version);
+		self
+	}
+
+	/// Don't connect with a version of TLS newer than this
+	pub fn max_version(mut self, version: TlsVersion) -> Self
+	{
+		self.max_version = Some(version);
+		self
+	}
+
+	/// The ciphers allowed for TLS 1.2 and older, in the
+	/// [format of the TLS library](https://curl.se/docs/ssl-ciphers.html)
+	pub fn ciphers(mut self, ciphers: impl Into<String>) -> Self
+	{
+		self.ciphers = Some(ciphers.into());
+		self
+	}
+
+	/// The cipher suites allowed for TLS 1.3, for example
+	/// `TLS_AES_256_GCM_SHA384:TLS_CHACHA20_POLY1305_SHA256`
+	pub fn tls13_ciphers(mut self, ciphers: impl Into<String>) -> Self
+	{
+		self.tls13_ciphers = Some(ciphers.into());
+		self
+	}
+
+	/// Only accept a server whose public key has this hash
+	///
+	/// The hash is the base64 encoded SHA-256 of the server's
+	/// DER encoded public key (its SubjectPublicKeyInfo), with or without
+	/// a `sha256//` prefix. It can be found with
+	///
+	/// ```text
+	/// openssl x509 -in cert.pem -pubkey -noout |
+	///   openssl pkey -pubin -outform der |
+	///   openssl dgst -sha256 -binary | base64
+	/// ```
+	///
+	/// Call this more than once to accept any of several keys, for
+	/// example to rotate keys. This replaces a
+	/// [`pinned_public_key_file`](#method.pinned_public_key_file). The
+	/// certificate is still verified as well, unless that is
+	/// [disabled](#method.danger_accept_invalid_certs).
+	///
+	/// If the server's key doesn't match, the request fails with
+	/// [`Kind::SslPinnedKeyMismatch`](enum.Kind.html#variant.SslPinnedKeyMismatch).
+	pub fn pin_sha256(mut self, hash: impl AsRef<str>) -> Self
+	{
+		let hash = hash.as_ref();
+		let hash = hash.strip_prefix("sha256//").unwrap_or(hash);
+		match &mut self.pinned_public_key
+		{
+			Some(Pin::Hashes(h)) => h.push(hash.to_string()),
+			_ => self.pinned_public_key = Some(Pin::Hashes(vec!(hash.to_string()))),
+		}
+		self
+	}
+
+	/// Only accept a server with the public key in this PEM or DER file
+	///
+	/// This replaces any [`pin_sha256`](#method.pin_sha256).
+	/// If the server's key doesn't match, the request fails with
+	/// [`Kind::SslPinnedKeyMismatch`](enum.Kind.html#variant.SslPinnedKeyMismatch).
+	pub fn pinned_public_key_file(mut self, path: impl AsRef<Path>) -> Self
+	{
+		self.pinned_public_key = Some(Pin::File(path.as_ref().to_path_buf()));
+		self
+	}
+
+	/// **Danger:** accept any certificate, even an expired,
+	/// self-signed or forged one
+	///
+	/// This makes TLS useless against an attacker, so it is
+	/// only suitable for testing.
+	pub fn danger_accept_invalid_certs(mut self, yes: bool) -> Self
+	{
+		self.accept_invalid_certs = yes;
+		self
+	}
+
+	/// **Danger:** accept a certificate that is for another host
+	///
+	/// This makes TLS useless against an attacker who has any
+	/// valid certificate, so it is only suitable for testing.
+	pub fn danger_accept_invalid_hostnames(mut self, yes: bool) -> Self
+	{
+		self.accept_invalid_hostnames = yes;
+		self
+	}
+
+	/// Keep the server's certificate chain so that it can be
+	/// inspected with [`Response::peer_certificates`]
+	///
+	/// This is off by default because it costs some time and memory
+	/// for every connection.
+	pub fn certificate_info(mut self, yes: bool) -> Self
+	{
+		self.certificate_info = yes;
+		self
+	}
+
+	/// Set the `options` of `easy`, which are for either the server or the proxy
+	pub(crate) unsafe fn apply(&self, easy: *mut sys::CURL, options: &TlsOptions) -> Result<()>
+	{
+		if let Some(path) = &self.ca_file
+			{ cr(sys::curl_easy_setopt(easy, options.cainfo, c_path(path)?.as_ptr()))?; }
+		if let Some(path) = &self.ca_dir
+			{ cr(sys::curl_easy_setopt(easy, options.capath, c_path(path)?.as_ptr()))?; }
+		if let Some(pem) = &self.ca_pem
+			{ set_blob(easy, options.cainfo_blob, pem)?; }
+
+		if let Some((source, format)) = &self.client_cert
+		{
+			match source
+			{
+				Source::File(path) =>
+					cr(sys::curl_easy_setopt(easy, options.sslcert, c_path(path)?.as_ptr()))?,
+				Source::Blob(b) => set_blob(easy, options.sslcert_blob, b)?,
+			}
+			cr(sys::curl_easy_setopt(easy, options.sslcerttype, format.curl().as_ptr()))?;
+		}
+		if let Some((source, format)) = &self.client_key
+		{
+			match source
+			{
+				Source::File(path) =>
+					cr(sys::curl_easy_setopt(easy, options.sslkey, c_path(path)?.as_ptr()))?,
+				Source::Blob(b) => set_blob(easy, options.sslkey_blob, b)?,
+			}
+			cr(sys::curl_easy_setopt(easy, options.sslkeytype, format.curl().as_ptr()))?;
+		}
+		if let Some(password) = &self.key_password
+		{
+			let password = c_string(password)?;
+			cr(sys::curl_easy_setopt(easy, options.keypasswd, password.as_ptr()))?;
+		}
+
+		if self.min_version.is_some() || self.max_version.is_some()
+		{
+			let min = self.min_version.map_or(sys::CURL_SSLVERSION_DEFAULT as c_long, |v| v.curl());
+			let max = self.max_version.map_or(0, |v| v.curl() << ffi::CURL_SSLVERSION_MAX_SHIFT);
+			cr(sys::curl_easy_setopt(easy, options.sslversion, min | max))?;
+		}
+		if let Some(ciphers) = &self.ciphers
+		{
+			let ciphers = c_string(ciphers)?;
+			cr(sys::curl_easy_setopt(easy, options.cipher_list, ciphers.as_ptr()))?;
+		}
+		if let Some(ciphers) = &self.tls13_ciphers
+		{
+			let ciphers = c_string(ciphers)?;
+			cr(sys::curl_easy_setopt(easy, options.tls13_ciphers, ciphers.as_ptr()))?;
+		}
+
+		match &self.pinned_public_key
+		{
+			Some(Pin::Hashes(hashes)) =>
+			{
+				let pins = hashes.iter()
+					.map(|h| format!("sha256//{}", h))
+					.collect::<Vec<_>>()
+					.join(";");
+				let pins = c_string(&pins)?;
+				cr(sys::curl_easy_setopt(easy, options.pinnedpublickey, pins.as_ptr()))?;
+			},
+			Some(Pin::File(path)) =>
+				cr(sys::curl_easy_setopt(easy, options.pinnedpublickey, c_path(path)?.as_ptr()))?,
+			None => {},
+		}
+
+		if self.accept_invalid_certs
+			{ cr(sys::curl_easy_setopt(easy, options.verifypeer, 0 as c_ulong))?; }
+		if self.accept_invalid_hostnames
+			{ cr(sys::curl_easy_setopt(easy, options.verifyhost, 0 as c_ulong))?; }
+		if let Some(certinfo) = options.certinfo.filter(|_| self.certificate_info)
+			{ cr(sys::curl_easy_setopt(easy, certinfo, 1 as c_long))?; }
+		Ok(())
+	}
+}
+
+/// The curl options that configure TLS with either the server or a proxy
+pub(crate) struct TlsOptions
+{
+	cainfo: sys::CURLoption,
+	capath: sys::CURLoption,
+	cainfo_blob: sys::CURLoption,
+	sslcert: sys::CURLoption,
+	sslcert_blob: sys::CURLoption,
+	sslcerttype: sys::CURLoption,
+	sslkey: sys::CURLoption,
+	sslkey_blob: sys::CURLoption,
+	sslkeytype: sys::CURLoption,
+	keypasswd: sys::CURLoption,
+	sslversion: sys::CURLoption,
+	cipher_list: sys::CURLoption,
+	tls13_ciphers: sys::CURLoption,
+	pinnedpublickey: sys::CURLoption,
+	verifypeer: sys::CURLoption,
+	verifyhost: sys::CURLoption,
+	/// curl can't keep a proxy's certificates
+	certinfo: Option<sys::CURLoption>,
+}
+
+pub(crate) const SERVER: TlsOptions = TlsOptions
+{
+	cainfo: sys::CURLOPT_CAINFO,
+	capath: sys::CURLOPT_CAPATH,
+	cainfo_blob: sys::CURLOPT_CAINFO_BLOB,
+	sslcert: sys::CURLOPT_SSLCERT,
+	sslcert_blob: sys::CURLOPT_SSLCERT_BLOB,
+	sslcerttype: sys::CURLOPT_SSLCERTTYPE,
+	sslkey: sys::CURLOPT_SSLKEY,
+	sslkey_blob: sys::CURLOPT_SSLKEY_BLOB,
+	sslkeytype: sys::CURLOPT_SSLKEYTYPE,
+	keypasswd: sys::CURLOPT_KEYPASSWD,
+	sslversion: sys::CURLOPT_SSLVERSION,
+	cipher_list: sys::CURLOPT_SSL_CIPHER_LIST,
+	tls13_ciphers: ffi::CURLOPT_TLS13_CIPHERS,
+	pinnedpublickey: sys::CURLOPT_PINNEDPUBLICKEY,
+	verifypeer: sys::CURLOPT_SSL_VERIFYPEER,
+	verifyhost: sys::CURLOPT_SSL_VERIFYHOST,
+	certinfo: Some(sys::CURLOPT_CERTINFO),
+};
+
+pub(crate) const PROXY: TlsOptions = TlsOptions
+{
+	cainfo: sys::CURLOPT_PROXY_CAINFO,
+	capath: sys::CURLOPT_PROXY_CAPATH,
+	cainfo_blob: sys::CURLOPT_PROXY_CAINFO_BLOB,
+	sslcert: sys::CURLOPT_PROXY_SSLCERT,
+	sslcert_blob: sys::CURLOPT_PROXY_SSLCERT_BLOB,
+	sslcerttype: sys::CURLOPT_PROXY_SSLCERTTYPE,
+	sslkey: sys::CURLOPT_PROXY_SSLKEY,
+	sslkey_blob: sys::CURLOPT_PROXY_SSLKEY_BLOB,
+	sslkeytype: sys::CURLOPT_PROXY_SSLKEYTYPE,
+	keypasswd: sys::CURLOPT_PROXY_KEYPASSWD,
+	sslversion: sys::CURLOPT_PROXY_SSLVERSION,
+	cipher_list: sys::CURLOPT_PROXY_SSL_CIPHER_LIST,
+	tls13_ciphers: ffi::CURLOPT_PROXY_TLS13_CIPHERS,
+	pinnedpublickey: ffi::CURLOPT_PROXY_PINNEDPUBLICKEY,
+	verifypeer: sys::CURLOPT_PROXY_SSL_VERIFYPEER,
+	verifyhost: sys::CURLOPT_PROXY_SSL_VERIFYHOST,
+	certinfo: None,
+};
+
+/// Give curl a copy of `data`
+unsafe fn set_blob(easy: *mut sys::CURL, option: sys::CURLoption, data: &[u8]) -> Result<()>
+{
+	let blob = sys::curl_blob
+	{
+		data: data.as_ptr() as *mut _,
+		len: data.len(),
+		flags: sys::CURL_BLOB_COPY,
+	};
+	cr(sys::curl_easy_setopt(easy, option, &blob as *const sys::curl_blob))
+}
+
+fn c_string(s: &str) -> Result<CString>
+{
+	CString::new(s)
+		.map_err(|_| Error::new(Kind::Curl("a TLS option contains a nul byte".to_string()), None))
+}
+
+pub(crate) fn c_path(path: &Path) -> Result<CString>
+{
+	#[cfg(unix)]
+	let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+	#[cfg(not(unix))]
+	let bytes = path.to_string_lossy().into_owned().into_bytes();
+
+	CString::new(bytes)
+		.map_err(|_| Error::new(Kind::Curl("a path contains a nul byte".to_string()), None))
+}
+
+impl Drop for TlsConfig
+{
+	fn drop(&mut self)
+	{
+		if let Some((Source::Blob(key), _)) = &mut self.client_key
+			{ crate::auth::zeroize(key); }
+		if let Some(password) = &mut self.key_password
+			{ crate::auth::zeroize(unsafe { password.as_mut_vec() }); }
+	}
+}
+
+impl std::fmt::Debug for TlsConfig
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		let source =
+			|s: &Option<(Source, CertificateFormat)>|
+				s.as_ref().map(
+					|(s, format)|
+						match s
+						{
+							Source::File(p) => format!("{:?} ({:?})", p, format),
+							Source::Blob(b) => format!("{} bytes ({:?})", b.len(), format),
+						}
+				);
+		f.debug_struct("TlsConfig")
+			.field("ca_file", &self.ca_file)
+			.field("ca_dir", &self.ca_dir)
+			.field("ca_pem", &self.ca_pem.as_ref().map(|p| p.len()))
+			.field("client_cert", &source(&self.client_cert))
+			.field("client_key", &source(&self.client_key))
+			.field("key_password", &self.key_password.as_ref().map(|_| "<redacted>"))
+			.field("min_version", &self.min_version)
+			.field("max_version", &self.max_version)
+			.field("ciphers", &self.ciphers)
+			.field("tls13_ciphers", &self.tls13_ciphers)
+			.field("pinned_public_key", &self.pinned_public_key)
+			.field("accept_invalid_certs", &self.accept_invalid_certs)
+			.field("accept_invalid_hostnames", &self.accept_invalid_hostnames)
+			.field("certificate_info", &self.certificate_info)
+			.finish()
+	}
+}
+
+/// One certificate of the chain that a server presented
+///
+/// These are returned by [`Response::peer_certificates`] when
+/// [`TlsConfig::certificate_info`] is enabled. The values are
+/// as the TLS library formats them, for example
+/// `"Jan  1 00:00:00 2030 GMT"` for a date.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct PeerCertificate
+{
+	fields: Vec<(String, String)>,
+}
+
+impl PeerCertificate
+{
+	/// Gets a field by the name that the TLS library gives it,
+	/// such as `"Subject"` or `"Signature Algorithm"`
+	pub fn field(&self, name: &str) -> Option<&str>
+	{
+		self.fields.iter()
+			.find(|(k, _)| k.eq_ignore_ascii_case(name))
+			.map(|(_, v)| v.as_str())
+	}
+
+	/// All the fields, in the order that the TLS library gave them
+	pub fn fields(&self) -> &[(String, String)]
+	{
+		&self.fields
+	}
+
+	/// The distinguished name of the certificate's owner
+	pub fn subject(&self) -> Option<&str>
+	{
+		self.field("Subject")
+	}
+
+	/// The distinguished name of the certificate's signer
+	pub fn issuer(&self) -> Option<&str>
+	{
+		self.field("Issuer")
+	}
+
+	/// The serial number, in hexadecimal
+	pub fn serial_number(&self) -> Option<&str>
+	{
+		self.field("Serial Number")
+	}
+
+	/// The time from which the certificate is valid
+	pub fn not_before(&self) -> Option<&str>
+	{
+		self.field("Start date")
+	}
+
+	/// The time after which the certificate is invalid
+	pub fn not_after(&self) -> Option<&str>
+	{
+		self.field("Expire date")
+	}
+
+	/// The subject alternative names, such as `"DNS:example.com"`
+	pub fn subject_alt_names(&self) -> Vec<&str>
+	{
+		self.field("X509v3 Subject Alternative Name")
+			.map(|s| s.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()).collect())
+			.unwrap_or_default()
+	}
+
+	/// The certificate in PEM format
+	pub fn pem(&self) -> Option<&str>
+	{
+		self.field("Cert")
+	}
+}
+
+/// The certificate chain of the connection that `easy` last used
+pub(crate) unsafe fn peer_certificates(easy: *mut sys::CURL) -> Vec<PeerCertificate>
+{
+	let mut info: *mut sys::curl_certinfo = std::ptr::null_mut();
+	if sys::curl_easy_getinfo(easy, sys::CURLINFO_CERTINFO, &mut info) != sys::CURLE_OK
+		|| info.is_null()
+		{ return vec!(); }
+
+	let info = &*info;
+	let mut certificates = vec!();
+	for i in 0 .. info.num_of_certs.max(0) as usize
+	{
+		let mut fields = vec!();
+		let mut item = *info.certinfo.add(i);
+		while !item.is_null()
+		{
+			let line = String::from_utf8_lossy(std::ffi::CStr::from_ptr((*item).data).to_bytes());
+			if let Some((k, v)) = line.split_once(':')
+				{ fields.push((k.to_string(), v.to_string())); }
+			item = (*item).next;
+		}
+		certificates.push(PeerCertificate { fields });
+	}
+	certificates
+}
+
+/// The TLS version and cipher that `easy`'s connection negotiated
+///
+/// These are only known with OpenSSL, and only during the transfer.
+/// The `tls-session` feature builds curl with the same OpenSSL as
+/// openssl-sys, so that its `SSL` can be given to openssl-sys.
+#[cfg(feature = "tls-session")]
+pub(crate) unsafe fn session(easy: *mut sys::CURL) -> Option<(String, String)>
+{
+	let mut info: *mut ffi::curl_tlssessioninfo = std::ptr::null_mut();
+	if sys::curl_easy_getinfo(easy, ffi::CURLINFO_TLS_SSL_PTR, &mut info) != sys::CURLE_OK
+		|| info.is_null()
+		|| (*info).backend != ffi::CURLSSLBACKEND_OPENSSL
+		|| (*info).internals.is_null()
+		{ return None; }
+
+	let ssl = (*info).internals as *const openssl_sys::SSL;
+	let string =
+		|p: *const libc::c_char|
+			(!p.is_null())
+				.then(|| std::ffi::CStr::from_ptr(p).to_string_lossy().into_owned());
+	let version = string(openssl_sys::SSL_get_version(ssl))?;
+	let cipher = openssl_sys::SSL_get_current_cipher(ssl);
+	if cipher.is_null()
+		{ return None; }
+	let cipher = string(openssl_sys::SSL_CIPHER_get_name(cipher))?;
+	Some((version, cipher))
+}
@@ -0,0 +1,197 @@
+use libc::{c_char,c_int,c_uint};
+
+use crate::*;
+
+/// A URL, as understood by curl
+///
+/// URLs are parsed with [curl's URL API](https://curl.se/libcurl/c/libcurl-url.html)
+/// so they are interpreted exactly the same way as they are when
+/// a request is made. A URL without a scheme is assumed to be `http`.
+#[derive(Clone,PartialEq,Eq,Hash)]
+pub struct Url
+{
+	url: String,
+	scheme: String,
+	host: Option<String>,
+	port: Option<u16>,
+	path: String,
+	query: Option<String>,
+}
+
+struct UrlHandle(*mut ffi::CURLU);
+
+impl UrlHandle
+{
+	fn new() -> UrlHandle
+	{
+		crate::init();
+		let h = unsafe { ffi::curl_url() };
+		assert!(!h.is_null());
+		UrlHandle(h)
+	}
+
+	fn set(&mut self, url: &str) -> Option<()>
+	{
+		let url = std::ffi::CString::new(url).ok()?;
+		let rc = unsafe
+		{
+			ffi::curl_url_set(
+				self.0,
+				ffi::CURLUPART_URL,
+				url.as_ptr(),
+				ffi::CURLU_GUESS_SCHEME | ffi::CURLU_NON_SUPPORT_SCHEME,
+			)
+		};
+		if rc == ffi::CURLUE_OK { Some(()) } else { None }
+	}
+
+	fn get(&self, part: c_uint, flags: c_uint) -> Option<String>
+	{
+		unsafe
+		{
+			let mut p: *mut c_char = std::ptr::null_mut();
+			if ffi::curl_url_get(self.0, part, &mut p, flags) != ffi::CURLUE_OK
+				{ return None; }
+			let s = std::ffi::CStr::from_ptr(p).to_string_lossy().into_owned();
+			sys::curl_free(p as *mut _);
+			Some(s)
+		}
+	}
+
+	fn to_url(&self) -> Option<Url>
+	{
+		Some(Url
+		{
+			url: self.get(ffi::CURLUPART_URL, 0)?,
+			scheme: self.get(ffi::CURLUPART_SCHEME, 0)?.to_ascii_lowercase(),
+			host: self.get(ffi::CURLUPART_HOST, 0)
+				.map(|h| h.to_ascii_lowercase()),
+			port: self.get(ffi::CURLUPART_PORT, ffi::CURLU_DEFAULT_PORT)
+				.and_then(|p| p.parse().ok()),
+			path: self.get(ffi::CURLUPART_PATH, 0)?,
+			query: self.get(ffi::CURLUPART_QUERY, 0),
+		})
+	}
+}
+
+impl Drop for UrlHandle
+{
+	fn drop(&mut self)
+	{
+		unsafe { ffi::curl_url_cleanup(self.0); }
+	}
+}
+
+impl Url
+{
+	/// Parse an absolute URL
+	pub fn parse(url: &str) -> Result<Url>
+	{
+		let mut h = UrlHandle::new();
+		h.set(url)
+			.and_then(|_| h.to_url())
+			.ok_or_else(|| Error::new(Kind::InvalidUrl, Some(url.to_string())))
+	}
+
+	/// Resolve a possibly relative URL (like the value of a `Location` header)
+	/// against this one
+	pub fn join(&self, relative: &str) -> Result<Url>
+	{
+		let mut h = UrlHandle::new();
+		h.set(&self.url)
+			.and_then(|_| h.set(relative))
+			.and_then(|_| h.to_url())
+			.ok_or_else(|| Error::new(Kind::InvalidUrl, Some(relative.to_string())))
+	}
+
+	/// The entire URL
+	pub fn as_str(&self) -> &str
+	{
+		&self.url
+	}
+
+	/// The scheme, in lowercase, for example `https`
+	pub fn scheme(&self) -> &str
+	{
+		&self.scheme
+	}
+
+	/// The host name or address, in lowercase
+	pub fn host(&self) -> Option<&str>
+	{
+		self.host.as_deref()
+	}
+
+	/// The port, which is the scheme's default if none was specified
+	pub fn port(&self) -> Option<u16>
+	{
+		self.port
+	}
+
+	/// The path, which always starts with `/`
+	pub fn path(&self) -> &str
+	{
+		&self.path
+	}
+
+	/// The query string without the leading `?`, if there is one
+	pub fn query(&self) -> Option<&str>
+	{
+		self.query.as_deref()
+	}
+
+	/// Returns true if the scheme, host and port of both URLs are the same
+	pub fn same_origin(&self, other: &Url) -> bool
+	{
+		self.scheme == other.scheme
+			&& self.host == other.host
+			&& self.port == other.port
+	}
+}
+
+/// Percent-encode everything in `s` except letters, digits and `-._~`
+pub(crate) fn escape(s: &str) -> String
+{
+	// curl takes a length of 0 to mean the string is nul-terminated
+	if s.is_empty()
+		{ return String::new(); }
+	crate::init();
+	unsafe
+	{
+		// the handle is not used by curl since 7.82
+		let p = sys::curl_easy_escape(
+			std::ptr::null_mut(),
+			s.as_ptr() as *const c_char,
+			s.len() as c_int,
+		);
+		assert!(!p.is_null());
+		let e = std::ffi::CStr::from_ptr(p).to_string_lossy().into_owned();
+		sys::curl_free(p as *mut _);
+		e
+	}
+}
+
+impl std::str::FromStr for Url
+{
+	type Err = Error;
+	fn from_str(s: &str) -> Result<Url>
+	{
+		Url::parse(s)
+	}
+}
+
+impl std::fmt::Display for Url
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.write_str(&self.url)
+	}
+}
+
+impl std::fmt::Debug for Url
+{
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result
+	{
+		f.write_fmt(format_args!("Url({:?})", self.url))
+	}
+}